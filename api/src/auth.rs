@@ -0,0 +1,309 @@
+//! 认证相关路由处理模块
+//!
+//! - 登录：校验邮箱和密码，签发访问令牌和刷新令牌
+//! - 刷新：轮换刷新令牌，旧令牌立即失效
+//! - 登出：吊销刷新令牌
+//! - 会话登录/登出：供服务端渲染页面使用，基于 Cookie 会话而非 Bearer 令牌
+
+use crate::error::{AppError, AppResult};
+use crate::openapi::schema;
+use crate::response::ApiResponse;
+use crate::validation::Valid;
+use async_trait::async_trait;
+use axum::{
+    Json,
+    extract::{Form, State},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{TimeDelta, Utc};
+use entity::user;
+use middleware::{
+    axum::{UserInfo, UserLoader},
+    jwt::JwtKeys,
+    session::SESSION_USER_KEY,
+};
+use sea_orm::{ActiveEnum, DatabaseConnection, DbErr};
+use serde::{Deserialize, Serialize};
+use service::{Mutation, Query};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::sync::{Arc, LazyLock};
+use tower_sessions::Session;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct LoginParams {
+    #[validate(length(min = 1, max = 255))]
+    pub email: String,
+    #[validate(length(min = 1, max = 72))]
+    pub password: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct RefreshParams {
+    #[validate(length(min = 1, max = 128))]
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    pub refresh_token: String,
+}
+
+fn session_error(e: tower_sessions::session::Error) -> AppError {
+    AppError::Internal(format!("Session error: {}", e))
+}
+
+fn invalid_refresh_token() -> AppError {
+    AppError::Unauthorized("Invalid refresh token".to_string())
+}
+
+fn user_info(user: &user::Model) -> UserInfo {
+    UserInfo {
+        user_id: user.id as u32,
+        username: user.name.clone(),
+        role: user.role.to_value(),
+    }
+}
+
+/// 从用户表读取会话用户的当前角色
+pub struct DbUserLoader(pub DatabaseConnection);
+
+#[async_trait]
+impl UserLoader for DbUserLoader {
+    async fn load_user(
+        &self,
+        user_id: u32,
+    ) -> Result<Option<UserInfo>, Box<dyn Error + Send + Sync>> {
+        let user = Query::find_user_by_id(&self.0, user_id as i32).await?;
+        Ok(user.as_ref().map(user_info))
+    }
+}
+
+/// 生成 256 位随机刷新令牌，返回 (令牌, 摘要)
+fn generate_refresh_token() -> (String, String) {
+    let token = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+    let token_hash = hash_refresh_token(&token);
+    (token, token_hash)
+}
+
+fn hash_refresh_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn refresh_expires_at(jwt: &JwtKeys) -> chrono::DateTime<Utc> {
+    Utc::now() + TimeDelta::seconds(jwt.refresh_expires_in() as i64)
+}
+
+fn access_token(jwt: &JwtKeys, user: &user::Model) -> Result<String, AppError> {
+    jwt.encode(&user_info(user)).map_err(|e| {
+        tracing::error!(error = %e, "Failed to sign access token");
+        AppError::Internal("Failed to issue token".to_string())
+    })
+}
+
+fn token_pair(jwt: &JwtKeys, access_token: String, refresh_token: String) -> TokenPair {
+    TokenPair {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: jwt.expires_in(),
+        refresh_token,
+    }
+}
+
+/// 用户不存在时用于比对的密码摘要，与注册时使用相同的代价，
+/// 使两种情况的耗时一致，避免通过响应时间判断账号是否存在
+pub(crate) static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+    bcrypt::hash(
+        URL_SAFE_NO_PAD.encode(rand::random::<[u8; 16]>()),
+        bcrypt::DEFAULT_COST,
+    )
+    .expect("Failed to hash dummy password")
+});
+
+/// 校验邮箱和密码
+async fn authenticate(
+    conn: &DatabaseConnection,
+    params: &LoginParams,
+) -> Result<user::Model, AppError> {
+    let user = Query::find_user_by_email(conn, &params.email).await?;
+
+    // 用户不存在和密码错误返回相同的信息，并且同样执行一次 bcrypt 校验，避免泄露账号是否存在
+    let hash = user
+        .as_ref()
+        .map_or(DUMMY_PASSWORD_HASH.as_str(), |user| user.password.as_str());
+    let verified = bcrypt::verify(&params.password, hash).unwrap_or(false);
+    match user {
+        Some(user) if verified => Ok(user),
+        _ => Err(AppError::Unauthorized(
+            "Invalid email or password".to_string(),
+        )),
+    }
+}
+
+/// 邮箱密码登录
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginParams,
+    responses(
+        (status = 200, body = ApiResponse<TokenPair>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn login(
+    State(conn): State<DatabaseConnection>,
+    State(jwt): State<Arc<JwtKeys>>,
+    Valid(Json(params)): Valid<Json<LoginParams>>,
+) -> AppResult<TokenPair> {
+    let user = authenticate(&conn, &params).await?;
+
+    let access_token = access_token(&jwt, &user)?;
+    let (refresh_token, token_hash) = generate_refresh_token();
+    Mutation::create_refresh_token(&conn, user.id, token_hash, refresh_expires_at(&jwt)).await?;
+
+    Ok(Json(ApiResponse::success_with_data(token_pair(
+        &jwt,
+        access_token,
+        refresh_token,
+    ))))
+}
+
+/// 使用刷新令牌换取新的令牌对
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshParams,
+    responses(
+        (status = 200, body = ApiResponse<TokenPair>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn refresh(
+    State(conn): State<DatabaseConnection>,
+    State(jwt): State<Arc<JwtKeys>>,
+    Valid(Json(params)): Valid<Json<RefreshParams>>,
+) -> AppResult<TokenPair> {
+    let stored =
+        Query::find_refresh_token_by_hash(&conn, &hash_refresh_token(&params.refresh_token))
+            .await?
+            .ok_or_else(invalid_refresh_token)?;
+
+    // 已吊销的令牌再次出现说明令牌可能泄露，吊销该用户的全部令牌
+    if stored.revoked_at.is_some() {
+        tracing::warn!(user_id = stored.user_id, "Revoked refresh token reused");
+        Mutation::revoke_user_refresh_tokens(&conn, stored.user_id).await?;
+        return Err(invalid_refresh_token());
+    }
+    if stored.expires_at <= Utc::now() {
+        return Err(AppError::Unauthorized(
+            "Refresh token has expired".to_string(),
+        ));
+    }
+
+    let user = Query::find_user_by_id(&conn, stored.user_id)
+        .await?
+        .ok_or_else(invalid_refresh_token)?;
+
+    let access_token = access_token(&jwt, &user)?;
+    let (refresh_token, token_hash) = generate_refresh_token();
+    match Mutation::rotate_refresh_token(
+        &conn,
+        stored.id,
+        user.id,
+        token_hash,
+        refresh_expires_at(&jwt),
+    )
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::success_with_data(token_pair(
+            &jwt,
+            access_token,
+            refresh_token,
+        )))),
+        // 并发请求已经轮换了该令牌
+        Err(DbErr::RecordNotUpdated) => Err(invalid_refresh_token()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 吊销刷新令牌
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    request_body = RefreshParams,
+    responses(
+        (status = 200, body = schema::MessageResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn logout(
+    State(conn): State<DatabaseConnection>,
+    Valid(Json(params)): Valid<Json<RefreshParams>>,
+) -> AppResult<()> {
+    let stored =
+        Query::find_refresh_token_by_hash(&conn, &hash_refresh_token(&params.refresh_token))
+            .await?;
+
+    if let Some(stored) = stored {
+        Mutation::revoke_refresh_token(&conn, stored.id).await?;
+    }
+
+    Ok(Json(ApiResponse::<()>::success_with_message(
+        "Logged out successfully".to_string(),
+    )))
+}
+
+/// 表单登录，将用户 ID 写入会话
+#[utoipa::path(
+    post,
+    path = "/auth/session",
+    tag = "auth",
+    request_body(content = LoginParams, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, body = ApiResponse<schema::UserInfo>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn session_login(
+    State(conn): State<DatabaseConnection>,
+    session: Session,
+    Valid(Form(params)): Valid<Form<LoginParams>>,
+) -> AppResult<UserInfo> {
+    let user = authenticate(&conn, &params).await?;
+    let user_info = user_info(&user);
+
+    // 登录后更换会话 ID，防止会话固定攻击
+    session.cycle_id().await.map_err(session_error)?;
+    session
+        .insert(SESSION_USER_KEY, user_info.user_id)
+        .await
+        .map_err(session_error)?;
+
+    Ok(Json(ApiResponse::success_with_data(user_info)))
+}
+
+/// 销毁当前会话
+#[utoipa::path(
+    delete,
+    path = "/auth/session",
+    tag = "auth",
+    responses((status = 200, body = schema::MessageResponse))
+)]
+pub async fn session_logout(session: Session) -> AppResult<()> {
+    session.flush().await.map_err(session_error)?;
+
+    Ok(Json(ApiResponse::<()>::success_with_message(
+        "Logged out successfully".to_string(),
+    )))
+}
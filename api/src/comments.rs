@@ -1,221 +1,278 @@
 use axum::{
-    extract::{Form, Path, Query, State},
-    http::StatusCode,
+    extract::{Form, OriginalUri, Path, Query, State},
     response::Json,
 };
 use entity::comment;
+use middleware::axum::{RequestContext, UserInfo};
 use sea_orm::{DatabaseConnection, TryIntoModel};
-use serde::{Deserialize, Serialize};
-use service::{Mutation as MutationCore, Query as QueryCore};
-
-use super::request::PageParams;
-use super::response::ApiResponse;
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CommentWithAuthor {
-    #[serde(flatten)]
-    pub comment: comment::Model,
-    pub author_name: String,
+use serde::Deserialize;
+use service::{CommentNode, CommentWithAuthor, Mutation as MutationCore, Query as QueryCore};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use super::error::{AppError, AppResult, PageResult};
+use super::openapi::schema;
+use super::permission::actor;
+use super::request::{ListParams, Listing, PageParams};
+use super::response::{ApiResponse, PageRes};
+use super::validation::Valid;
+
+/// 发表或修改评论的请求参数
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CommentInput {
+    #[validate(length(min = 1, max = 5000))]
+    pub content: String,
+    /// 回复的评论，修改评论时忽略
+    pub parent_id: Option<i32>,
+}
+
+/// 评论树的展开参数
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TreeParams {
+    /// 展开的回复层数，默认 2 层，最多 5 层
+    pub depth: Option<u32>,
+    /// 每条评论展开的回复数，默认 3 条，最多 50 条
+    pub replies: Option<u64>,
 }
 
+const DEFAULT_DEPTH: u32 = 2;
+const MAX_DEPTH: u32 = 5;
+const DEFAULT_REPLIES: u64 = 3;
+const MAX_REPLIES: u64 = 50;
+
 // API handlers for Comments
 
+#[utoipa::path(
+    get,
+    path = "/posts/{post_id}/comments",
+    tag = "comments",
+    params(("post_id" = i32, Path, description = "文章 ID"), PageParams, TreeParams, ListParams),
+    responses(
+        (status = 200, body = ApiResponse<PageRes<Vec<schema::CommentNode>>>),
+        (status = 400, description = "Invalid filter or sort", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn list(
     State(conn): State<DatabaseConnection>,
+    OriginalUri(uri): OriginalUri,
     Path(post_id): Path<i32>,
-    Query(params): Query<PageParams>,
-) -> Result<
-    Json<ApiResponse<Vec<CommentWithAuthor>>>,
-    (StatusCode, Json<ApiResponse<Vec<CommentWithAuthor>>>),
-> {
-    let page = params.page.unwrap_or(1);
-    let comments_per_page = params.size.unwrap_or(5);
-
-    match QueryCore::find_comments_by_post_id_in_page(&conn, post_id, page, comments_per_page).await
-    {
-        Ok((comments, _num_pages)) => {
-            let mut comments_with_author: Vec<CommentWithAuthor> = Vec::new();
-
-            for comment in comments {
-                match QueryCore::find_user_by_id(&conn, comment.user_id).await {
-                    Ok(Some(author)) => {
-                        comments_with_author.push(CommentWithAuthor {
-                            comment,
-                            author_name: author.name,
-                        });
-                    }
-                    Ok(None) => {
-                        // Handle case where author is not found
-                        comments_with_author.push(CommentWithAuthor {
-                            comment,
-                            author_name: "Unknown".to_string(),
-                        });
-                    }
-                    Err(_) => {
-                        // Handle database error
-                        comments_with_author.push(CommentWithAuthor {
-                            comment,
-                            author_name: "Unknown".to_string(),
-                        });
-                    }
-                }
-            }
-
-            Ok(Json(ApiResponse::success_with_data(comments_with_author)))
-        }
-        Err(e) => {
-            let error_response = ApiResponse::<Vec<CommentWithAuthor>>::error_with_message(
-                format!("Database error: {}", e),
-            );
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
+    Valid(Query(params)): Valid<Query<PageParams>>,
+    Query(tree): Query<TreeParams>,
+    listing: Listing<comment::Entity>,
+) -> PageResult<CommentNode> {
+    let nodes = comment_tree(&conn, post_id, None, params, tree, listing).await?;
+    Ok(nodes.with_links(&uri))
+}
+
+// 分页获取某条评论下的回复
+#[utoipa::path(
+    get,
+    path = "/posts/{post_id}/comments/{comment_id}/replies",
+    tag = "comments",
+    params(
+        ("post_id" = i32, Path, description = "文章 ID"),
+        ("comment_id" = i32, Path, description = "评论 ID"),
+        PageParams,
+        TreeParams,
+        ListParams,
+    ),
+    responses(
+        (status = 200, body = ApiResponse<PageRes<Vec<schema::CommentNode>>>),
+        (status = 400, description = "Invalid filter or sort", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn replies(
+    State(conn): State<DatabaseConnection>,
+    OriginalUri(uri): OriginalUri,
+    Path((post_id, comment_id)): Path<(i32, i32)>,
+    Valid(Query(params)): Valid<Query<PageParams>>,
+    Query(tree): Query<TreeParams>,
+    listing: Listing<comment::Entity>,
+) -> PageResult<CommentNode> {
+    QueryCore::find_comment_by_id(&conn, comment_id)
+        .await?
+        .filter(|comment| comment.post_id == post_id)
+        .ok_or_else(|| AppError::NotFound("Comment not found".to_string()))?;
+
+    let nodes = comment_tree(&conn, post_id, Some(comment_id), params, tree, listing).await?;
+    Ok(nodes.with_links(&uri))
+}
+
+async fn comment_tree(
+    conn: &DatabaseConnection,
+    post_id: i32,
+    parent_id: Option<i32>,
+    params: PageParams,
+    tree: TreeParams,
+    listing: Listing<comment::Entity>,
+) -> Result<PageRes<Vec<CommentNode>>, AppError> {
+    let page = params.page();
+    let comments_per_page = params.size(5);
+    let depth = tree.depth.unwrap_or(DEFAULT_DEPTH).min(MAX_DEPTH);
+    let replies = tree.replies.unwrap_or(DEFAULT_REPLIES).min(MAX_REPLIES);
+
+    let nodes = match listing.check_cursor(params.cursor()?)? {
+        Some(cursor) => PageRes::from_cursor(
+            QueryCore::find_comment_tree_with_cursor(
+                conn,
+                post_id,
+                parent_id,
+                &listing.0,
+                Some(cursor),
+                comments_per_page,
+                depth,
+                replies,
+            )
+            .await?,
+            comments_per_page,
+        ),
+        None => {
+            let (nodes, totals) = QueryCore::find_comment_tree_in_page(
+                conn,
+                post_id,
+                parent_id,
+                &listing.0,
+                page,
+                comments_per_page,
+                depth,
+                replies,
+            )
+            .await?;
+            listing.from_page(nodes, page, comments_per_page, totals, |node| {
+                node.comment.comment.id
+            })
         }
-    }
+    };
+
+    Ok(nodes)
 }
 
+#[utoipa::path(
+    post,
+    path = "/posts/{post_id}/comments",
+    tag = "comments",
+    params(("post_id" = i32, Path, description = "文章 ID")),
+    request_body(content = CommentInput, content_type = "application/x-www-form-urlencoded"),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<schema::CommentWithAuthor>),
+        (status = 400, description = "Invalid request", body = schema::ErrorResponse),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn create(
     State(conn): State<DatabaseConnection>,
+    user: UserInfo,
     Path(post_id): Path<i32>,
-    Form(input): Form<comment::Model>,
-) -> Result<Json<ApiResponse<CommentWithAuthor>>, (StatusCode, Json<ApiResponse<CommentWithAuthor>>)>
-{
+    Valid(Form(input)): Valid<Form<CommentInput>>,
+) -> AppResult<CommentWithAuthor> {
+    // The author and post always come from the caller and the path,
+    // `parent_id` makes the comment a reply
+    let form_data = comment::Model {
+        id: 0,
+        user_id: user.user_id as i32,
+        post_id,
+        parent_id: input.parent_id,
+        content: input.content,
+        deleted_at: None,
+    };
+
     // First check if the post exists
-    match QueryCore::find_post_by_id(&conn, post_id).await {
-        Ok(Some(_post)) => {
-            // Create the comment
-            match MutationCore::create_comment(&conn, input).await {
-                Ok(comment_active_model) => {
-                    match comment_active_model.try_into_model() {
-                        Ok(comment_model) => {
-                            // Get the author name
-                            match QueryCore::find_user_by_id(&conn, comment_model.user_id).await {
-                                Ok(Some(author)) => {
-                                    let comment_with_author = CommentWithAuthor {
-                                        comment: comment_model,
-                                        author_name: author.name,
-                                    };
-                                    Ok(Json(ApiResponse::success_with_data(comment_with_author)))
-                                }
-                                Ok(None) => {
-                                    let error_response =
-                                        ApiResponse::<CommentWithAuthor>::error_with_message(
-                                            "Author not found".to_string(),
-                                        );
-                                    Err((StatusCode::NOT_FOUND, Json(error_response)))
-                                }
-                                Err(e) => {
-                                    let error_response =
-                                        ApiResponse::<CommentWithAuthor>::error_with_message(
-                                            format!("Database error: {}", e),
-                                        );
-                                    Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            let error_response =
-                                ApiResponse::<CommentWithAuthor>::error_with_message(format!(
-                                    "Conversion error: {}",
-                                    e
-                                ));
-                            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                        }
-                    }
-                }
-                Err(e) => {
-                    let error_response = ApiResponse::<CommentWithAuthor>::error_with_message(
-                        format!("Database error: {}", e),
-                    );
-                    Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                }
-            }
-        }
-        Ok(None) => {
-            let error_response =
-                ApiResponse::<CommentWithAuthor>::error_with_message("Post not found".to_string());
-            Err((StatusCode::NOT_FOUND, Json(error_response)))
-        }
-        Err(e) => {
-            let error_response = ApiResponse::<CommentWithAuthor>::error_with_message(format!(
-                "Database error: {}",
-                e
-            ));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-        }
-    }
+    QueryCore::find_post_model_by_id(&conn, post_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    // Create the comment
+    let comment_model = MutationCore::create_comment(&conn, form_data)
+        .await?
+        .try_into_model()?;
+
+    // The author is the caller, no need to look it up again
+    Ok(Json(ApiResponse::success_with_data(CommentWithAuthor {
+        comment: comment_model,
+        author_name: user.username,
+    })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/posts/{post_id}/comments/{comment_id}",
+    tag = "comments",
+    params(
+        ("post_id" = i32, Path, description = "文章 ID"),
+        ("comment_id" = i32, Path, description = "评论 ID"),
+    ),
+    request_body(content = CommentInput, content_type = "application/x-www-form-urlencoded"),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<schema::CommentWithAuthor>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn update(
     State(conn): State<DatabaseConnection>,
+    user: UserInfo,
+    ctx: RequestContext,
     Path((post_id, comment_id)): Path<(i32, i32)>,
-    Form(input): Form<comment::Model>,
-) -> Result<Json<ApiResponse<CommentWithAuthor>>, (StatusCode, Json<ApiResponse<CommentWithAuthor>>)>
-{
-    // Check if the post exists
-    match QueryCore::find_post_by_id(&conn, post_id).await {
-        Ok(Some(_post)) => {
-            // Update the comment
-            match MutationCore::update_comment_by_id(&conn, comment_id, input).await {
-                Ok(comment_model) => {
-                    // Get the author name
-                    match QueryCore::find_user_by_id(&conn, comment_model.user_id).await {
-                        Ok(Some(author)) => {
-                            let comment_with_author = CommentWithAuthor {
-                                comment: comment_model,
-                                author_name: author.name,
-                            };
-                            Ok(Json(ApiResponse::success_with_data(comment_with_author)))
-                        }
-                        Ok(None) => {
-                            let error_response =
-                                ApiResponse::<CommentWithAuthor>::error_with_message(
-                                    "Author not found".to_string(),
-                                );
-                            Err((StatusCode::NOT_FOUND, Json(error_response)))
-                        }
-                        Err(e) => {
-                            let error_response =
-                                ApiResponse::<CommentWithAuthor>::error_with_message(format!(
-                                    "Database error: {}",
-                                    e
-                                ));
-                            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                        }
-                    }
-                }
-                Err(e) => {
-                    let error_response = ApiResponse::<CommentWithAuthor>::error_with_message(
-                        format!("Database error: {}", e),
-                    );
-                    Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                }
-            }
-        }
-        Ok(None) => {
-            let error_response =
-                ApiResponse::<CommentWithAuthor>::error_with_message("Post not found".to_string());
-            Err((StatusCode::NOT_FOUND, Json(error_response)))
-        }
-        Err(e) => {
-            let error_response = ApiResponse::<CommentWithAuthor>::error_with_message(format!(
-                "Database error: {}",
-                e
-            ));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-        }
-    }
+    Valid(Form(input)): Valid<Form<CommentInput>>,
+) -> AppResult<CommentWithAuthor> {
+    let form_data = comment::Model {
+        id: comment_id,
+        user_id: user.user_id as i32,
+        post_id,
+        parent_id: None,
+        content: input.content,
+        deleted_at: None,
+    };
+
+    // Update the comment, only its author (or a moderator) may do so
+    let comment_with_author = MutationCore::update_comment_by_id(
+        &conn,
+        &actor(&user, &ctx),
+        post_id,
+        comment_id,
+        form_data,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success_with_data(comment_with_author)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/posts/{post_id}/comments/{comment_id}",
+    tag = "comments",
+    params(
+        ("post_id" = i32, Path, description = "文章 ID"),
+        ("comment_id" = i32, Path, description = "评论 ID"),
+    ),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = schema::MessageResponse),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+    )
+)]
 pub async fn delete(
     State(conn): State<DatabaseConnection>,
-    Path((_post_id, comment_id)): Path<(i32, i32)>,
-) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
-    match MutationCore::delete_comment(&conn, comment_id).await {
-        Ok(_) => Ok(Json(ApiResponse::<()>::success_with_message(
-            "Comment deleted successfully".to_string(),
-        ))),
-        Err(e) => {
-            let error_response =
-                ApiResponse::<()>::error_with_message(format!("Database error: {}", e));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-        }
-    }
+    user: UserInfo,
+    ctx: RequestContext,
+    Path((post_id, comment_id)): Path<(i32, i32)>,
+) -> AppResult<()> {
+    MutationCore::delete_comment(&conn, &actor(&user, &ctx), post_id, comment_id).await?;
+
+    Ok(Json(ApiResponse::<()>::success_with_message(
+        "Comment deleted successfully".to_string(),
+    )))
 }
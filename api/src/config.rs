@@ -0,0 +1,439 @@
+//! 应用配置
+//!
+//! 配置项按以下顺序加载，后加载的覆盖先加载的：
+//!
+//! 1. 代码中的默认值，见 `DEFAULTS`
+//! 2. TOML 配置文件，路径由 `CONFIG_FILE` 指定，未指定时读取当前目录下的 `config.toml`（不存在时跳过）
+//! 3. `.env` 文件，设置了 `APP_ENV` 时再加载 `.env.{APP_ENV}`（如 `.env.test`）覆盖其中的值
+//! 4. 环境变量
+//! 5. 命令行参数
+//!
+//! 各来源使用同一套键名，即环境变量名。TOML 中的键为其小写形式，表名作为前缀，
+//! 例如 `[jwt]` 表中的 `secret` 与顶层的 `jwt_secret` 都对应 `JWT_SECRET`；
+//! 命令行参数写作 `--jwt-secret value` 或 `--jwt-secret=value`。
+//!
+//! 全部来源合并后统一解析和校验，缺失或不合法的配置项会一次性列出。
+
+use middleware::context::TrustedProxies;
+use middleware::jwt::{JwtConfig, JwtKeys};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing_subscriber::filter::LevelFilter;
+use uitls::Dotenv;
+
+/// 默认值，未列出的配置项没有默认值
+pub const DEFAULTS: &[(&str, &str)] = &[
+    ("HOST", "127.0.0.1"),
+    ("PORT", "3000"),
+    ("SESSION_TTL", "604800"),
+    ("SESSION_SECURE", "true"),
+    ("LOG_LEVEL", "info"),
+    ("ENABLE_CONSOLE_LOG", "true"),
+    ("ENABLE_FILE_LOG", "false"),
+    ("ENABLE_OPENTELEMETRY", "false"),
+    ("LOG_DIR", "./logs"),
+    ("LOG_FILE_NAME", "app.log"),
+    ("APP_ENV", "development"),
+    ("OTEL_SERVICE_NAME", "rs-web"),
+    ("OTEL_TRACES_EXPORTER", "otlp"),
+    ("OTEL_EXPORTER_OTLP_PROTOCOL", "grpc"),
+    ("OTEL_TRACES_SAMPLER_ARG", "1.0"),
+    ("HEALTH_CHECK_MIGRATIONS", "false"),
+    ("SHUTDOWN_DELAY", "5"),
+    (
+        "METRICS_ALLOW",
+        "127.0.0.1,::1,10.0.0.0/8,172.16.0.0/12,192.168.0.0/16",
+    ),
+];
+
+/// 未指定 `CONFIG_FILE` 时读取的配置文件
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+pub struct Config {
+    /// 监听地址 `HOST` / `PORT`
+    pub host: String,
+    pub port: u16,
+    /// `TRUSTED_PROXIES`，逗号分隔的反向代理地址或网段，只有来自这些地址的请求
+    /// 才从 `X-Forwarded-For` 中读取客户端地址，未设置时不信任任何代理
+    pub trusted_proxies: TrustedProxies,
+    /// `METRICS_ALLOW`，允许抓取 `/metrics` 的地址或网段，语法同 `TRUSTED_PROXIES`，
+    /// 默认为本机和私有网段，设为空时禁止访问
+    pub metrics_allow: TrustedProxies,
+    /// `DATABASE_URL`，必填
+    pub database_url: String,
+    pub session: SessionConfig,
+    /// `JWT_*`，见 `JwtConfig::from_env`
+    pub jwt: JwtConfig,
+    pub log: LogConfig,
+    pub telemetry: TelemetryConfig,
+    pub health: HealthConfig,
+}
+
+pub struct SessionConfig {
+    /// `REDIS_URL`，未设置时会话保存在内存中
+    pub redis_url: Option<String>,
+    /// `SESSION_TTL`，无操作多少秒后会话过期
+    pub ttl: i64,
+    /// `SESSION_SECURE`，会话 Cookie 是否只通过 HTTPS 发送
+    pub secure: bool,
+}
+
+pub struct LogConfig {
+    /// `CONSOLE_LOG_LEVEL`，未设置时使用 `LOG_LEVEL`
+    pub console_level: LevelFilter,
+    /// `FILE_LOG_LEVEL`，未设置时使用 `LOG_LEVEL`
+    pub file_level: LevelFilter,
+    /// `LOG_LEVEL`，也用于 OpenTelemetry 追踪层
+    pub level: LevelFilter,
+    /// `ENABLE_CONSOLE_LOG`
+    pub console: bool,
+    /// `ENABLE_FILE_LOG`
+    pub file: bool,
+    /// `ENABLE_OPENTELEMETRY`
+    pub opentelemetry: bool,
+    /// `LOG_DIR`，日志文件按天滚动
+    pub dir: PathBuf,
+    /// `LOG_FILE_NAME`
+    pub file_name: String,
+}
+
+pub struct HealthConfig {
+    /// `HEALTH_CHECK_MIGRATIONS`，就绪检查是否要求没有未执行的迁移，
+    /// 启动时只执行 schema sync、不执行迁移，因此默认关闭
+    pub check_migrations: bool,
+    /// `SHUTDOWN_DELAY`，收到退出信号后就绪检查先失败多少秒，再停止接收新连接，
+    /// 留出时间让负载均衡摘除本实例
+    pub shutdown_delay: u64,
+}
+
+/// OpenTelemetry 追踪导出，`ENABLE_OPENTELEMETRY` 开启时生效
+pub struct TelemetryConfig {
+    /// `OTEL_TRACES_EXPORTER`（`otlp` / `stdout`）与 `OTEL_EXPORTER_OTLP_PROTOCOL`（`grpc` / `http/protobuf`）
+    pub exporter: TraceExporter,
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`，未设置时使用 gRPC 的 `http://localhost:4317`
+    /// 或 HTTP 的 `http://localhost:4318`，HTTP 会在其后加上 `/v1/traces`
+    pub endpoint: Option<String>,
+    /// `OTEL_SERVICE_NAME`
+    pub service_name: String,
+    /// `APP_ENV`，作为资源属性 `deployment.environment.name`
+    pub environment: String,
+    /// `OTEL_TRACES_SAMPLER_ARG`，0 到 1 之间的采样比例，上游已决定采样的请求沿用上游的决定
+    pub sample_ratio: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceExporter {
+    OtlpGrpc,
+    OtlpHttp,
+    /// 输出到标准输出，用于本地调试
+    Stdout,
+}
+
+/// 配置错误，包含全部缺失或不合法的配置项
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n  - {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// 从全部来源加载配置
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+        let mut layer = |result: Result<HashMap<String, String>, String>| {
+            result.unwrap_or_else(|e| {
+                errors.push(e);
+                HashMap::new()
+            })
+        };
+
+        let args = layer(args_vars(std::env::args().skip(1)));
+        let env: HashMap<String, String> = std::env::vars().collect();
+        let profile = [&args, &env]
+            .into_iter()
+            .find_map(|vars| vars.get("APP_ENV"))
+            .cloned()
+            .unwrap_or_default();
+        let dotenv = layer(
+            Dotenv::new()
+                .profile(profile)
+                .read()
+                .map_err(|e| e.to_string()),
+        );
+
+        // 配置文件路径本身只能来自后面几个来源
+        let toml = match [&args, &env, &dotenv]
+            .into_iter()
+            .find_map(|vars| vars.get("CONFIG_FILE"))
+        {
+            Some(path) => layer(read_toml(Path::new(path))),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                layer(read_toml(Path::new(DEFAULT_CONFIG_FILE)))
+            }
+            None => HashMap::new(),
+        };
+
+        let mut vars: HashMap<String, String> = DEFAULTS
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        for source in [toml, dotenv, env, args] {
+            vars.extend(source);
+        }
+
+        match Config::from_vars(&vars) {
+            Ok(config) if errors.is_empty() => Ok(config),
+            Ok(_) => Err(ConfigError(errors)),
+            Err(ConfigError(mut invalid)) => {
+                errors.append(&mut invalid);
+                Err(ConfigError(errors))
+            }
+        }
+    }
+
+    /// 解析并校验合并后的配置项，不再补充默认值
+    pub fn from_vars(vars: &HashMap<String, String>) -> Result<Self, ConfigError> {
+        let mut reader = Reader {
+            vars,
+            errors: Vec::new(),
+        };
+
+        let host = reader.required("HOST");
+        let port = reader.parse::<u16>("PORT");
+        if port == Some(0) {
+            reader.invalid("PORT", "must not be 0");
+        }
+        let trusted_proxies = reader.parse_or("TRUSTED_PROXIES", TrustedProxies::default());
+        let metrics_allow = reader.parse_or("METRICS_ALLOW", TrustedProxies::default());
+        let database_url = reader.required("DATABASE_URL");
+
+        let session = SessionConfig {
+            redis_url: reader.get("REDIS_URL").map(str::to_string),
+            ttl: reader.parse("SESSION_TTL").unwrap_or_default(),
+            secure: reader.flag("SESSION_SECURE"),
+        };
+        if session.ttl < 0 {
+            reader.invalid("SESSION_TTL", "must not be negative");
+        }
+
+        let level = reader
+            .parse::<LevelFilter>("LOG_LEVEL")
+            .unwrap_or(LevelFilter::INFO);
+        let log = LogConfig {
+            console_level: reader.parse_or("CONSOLE_LOG_LEVEL", level),
+            file_level: reader.parse_or("FILE_LOG_LEVEL", level),
+            level,
+            console: reader.flag("ENABLE_CONSOLE_LOG"),
+            file: reader.flag("ENABLE_FILE_LOG"),
+            opentelemetry: reader.flag("ENABLE_OPENTELEMETRY"),
+            dir: PathBuf::from(reader.required("LOG_DIR")),
+            file_name: reader.required("LOG_FILE_NAME"),
+        };
+
+        let exporter = match (
+            reader.required("OTEL_TRACES_EXPORTER").as_str(),
+            reader.required("OTEL_EXPORTER_OTLP_PROTOCOL").as_str(),
+        ) {
+            ("stdout", _) => TraceExporter::Stdout,
+            ("otlp", "grpc") => TraceExporter::OtlpGrpc,
+            ("otlp", "http/protobuf") => TraceExporter::OtlpHttp,
+            ("otlp", protocol) => {
+                let reason = format!("must be grpc or http/protobuf, got `{protocol}`");
+                reader.invalid("OTEL_EXPORTER_OTLP_PROTOCOL", &reason);
+                TraceExporter::OtlpGrpc
+            }
+            (exporter, _) => {
+                let reason = format!("must be otlp or stdout, got `{exporter}`");
+                reader.invalid("OTEL_TRACES_EXPORTER", &reason);
+                TraceExporter::OtlpGrpc
+            }
+        };
+        let telemetry = TelemetryConfig {
+            exporter,
+            endpoint: reader
+                .get("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .map(str::to_string),
+            service_name: reader.required("OTEL_SERVICE_NAME"),
+            environment: reader.required("APP_ENV"),
+            sample_ratio: reader.parse("OTEL_TRACES_SAMPLER_ARG").unwrap_or(1.0),
+        };
+        if !(0.0..=1.0).contains(&telemetry.sample_ratio) {
+            reader.invalid("OTEL_TRACES_SAMPLER_ARG", "must be between 0 and 1");
+        }
+
+        let health = HealthConfig {
+            check_migrations: reader.flag("HEALTH_CHECK_MIGRATIONS"),
+            shutdown_delay: reader.parse("SHUTDOWN_DELAY").unwrap_or_default(),
+        };
+
+        // 密钥长度等在构建密钥时才校验，这里提前构建一次
+        let jwt = JwtConfig::from_vars(|key| vars.get(key).cloned())
+            .and_then(|jwt| JwtKeys::new(&jwt).map(|_| jwt));
+        let jwt = match jwt {
+            Ok(jwt) => Some(jwt),
+            Err(e) => {
+                reader.errors.push(e.to_string());
+                None
+            }
+        };
+
+        match (reader.errors.is_empty(), port, jwt) {
+            (true, Some(port), Some(jwt)) => Ok(Config {
+                host,
+                port,
+                trusted_proxies,
+                metrics_allow,
+                database_url,
+                session,
+                jwt,
+                log,
+                telemetry,
+                health,
+            }),
+            _ => Err(ConfigError(reader.errors)),
+        }
+    }
+
+    /// 监听地址
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// 按键读取配置项，记录遇到的全部错误
+struct Reader<'a> {
+    vars: &'a HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl Reader<'_> {
+    /// 空值视为未设置
+    fn get(&self, key: &str) -> Option<&str> {
+        self.vars
+            .get(key)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn invalid(&mut self, key: &str, reason: &str) {
+        self.errors.push(format!("{key} {reason}"));
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        match self.get(key) {
+            Some(value) => value.to_string(),
+            None => {
+                self.invalid(key, "is not set");
+                String::new()
+            }
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        let value = self.required(key);
+        if value.is_empty() {
+            return None;
+        }
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            self.invalid(key, &format!("has an invalid value `{value}`"));
+        }
+        parsed
+    }
+
+    /// 未设置时使用 `default`
+    fn parse_or<T: FromStr>(&mut self, key: &str, default: T) -> T {
+        match self.get(key) {
+            Some(_) => self.parse(key).unwrap_or(default),
+            None => default,
+        }
+    }
+
+    fn flag(&mut self, key: &str) -> bool {
+        match self.get(key).map(str::to_lowercase).as_deref() {
+            Some("true" | "1" | "yes" | "on") => true,
+            Some("false" | "0" | "no" | "off") => false,
+            Some(value) => {
+                let reason = format!("must be true or false, got `{value}`");
+                self.invalid(key, &reason);
+                false
+            }
+            None => {
+                self.invalid(key, "is not set");
+                false
+            }
+        }
+    }
+}
+
+/// 将 `--some-key value` / `--some-key=value` 形式的命令行参数转换为 `SOME_KEY`
+pub fn args_vars(
+    args: impl IntoIterator<Item = String>,
+) -> Result<HashMap<String, String>, String> {
+    let mut vars = HashMap::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let flag = arg
+            .strip_prefix("--")
+            .ok_or_else(|| format!("Unexpected argument `{arg}`"))?;
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, value.to_string()),
+            None => (
+                flag,
+                args.next()
+                    .ok_or_else(|| format!("Missing value for `--{flag}`"))?,
+            ),
+        };
+        vars.insert(name.replace('-', "_").to_uppercase(), value);
+    }
+    Ok(vars)
+}
+
+/// 将 TOML 配置展开为键值，表名作为键的前缀
+pub fn toml_vars(content: &str) -> Result<HashMap<String, String>, String> {
+    let table: toml::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+    let mut vars = HashMap::new();
+    flatten_toml("", table, &mut vars)?;
+    Ok(vars)
+}
+
+fn read_toml(path: &Path) -> Result<HashMap<String, String>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    toml_vars(&content).map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+}
+
+fn flatten_toml(
+    prefix: &str,
+    table: toml::Table,
+    vars: &mut HashMap<String, String>,
+) -> Result<(), String> {
+    for (key, value) in table {
+        let key = format!("{prefix}{}", key.replace('-', "_").to_uppercase());
+        let value = match value {
+            toml::Value::Table(table) => {
+                flatten_toml(&format!("{key}_"), table, vars)?;
+                continue;
+            }
+            toml::Value::String(s) => s,
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Float(f) => f.to_string(),
+            toml::Value::Boolean(b) => b.to_string(),
+            toml::Value::Datetime(d) => d.to_string(),
+            toml::Value::Array(_) => return Err(format!("{key}: arrays are not supported")),
+        };
+        vars.insert(key, value);
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! 统一错误类型
+//!
+//! 处理函数返回 `AppResult<T>`，通过 `?` 传播错误，
+//! 由 `IntoResponse` 统一转换为状态码与 `code` 一致的 `ApiResponse`。
+//! 服务端错误（5xx）只记录到日志，响应中不返回数据库等内部细节。
+
+use crate::response::{ApiResponse, PageResponse};
+use crate::validation::FieldError;
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use sea_orm::{DbErr, SqlErr};
+use service::ServiceError;
+use std::fmt;
+
+pub type AppResult<T> = Result<Json<ApiResponse<T>>, AppError>;
+
+/// 分页接口的返回值，见 `PageRes::with_links`
+pub type PageResult<T> = Result<PageResponse<T>, AppError>;
+
+#[derive(Debug)]
+pub enum AppError {
+    /// 资源不存在，404
+    NotFound(String),
+    /// 与已有数据冲突，例如唯一键重复，409
+    Conflict(String),
+    /// 请求参数不合法，400
+    Validation(String),
+    /// 请求参数未通过字段校验，422，字段错误放在 `data` 中
+    InvalidFields(Vec<FieldError>),
+    /// 未登录或凭证无效，401
+    Unauthorized(String),
+    /// 无权执行该操作，403
+    Forbidden(String),
+    /// 数据库错误，500
+    Database(DbErr),
+    /// 其他服务端错误，500
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// 约束冲突转换为 409，其他 SQL 错误返回 `None`
+    pub fn from_sql_err(e: &SqlErr) -> Option<AppError> {
+        match e {
+            SqlErr::UniqueConstraintViolation(_) => {
+                Some(AppError::Conflict("Record already exists".to_string()))
+            }
+            SqlErr::ForeignKeyConstraintViolation(_) => Some(AppError::Conflict(
+                "Record is referenced by other data".to_string(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Database(e) => write!(f, "Database error: {e}"),
+            AppError::InvalidFields(_) => write!(f, "Validation failed"),
+            AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::Validation(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::Internal(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<DbErr> for AppError {
+    fn from(e: DbErr) -> Self {
+        if let Some(conflict) = e.sql_err().as_ref().and_then(AppError::from_sql_err) {
+            return conflict;
+        }
+        match e {
+            DbErr::RecordNotFound(msg) => AppError::NotFound(msg),
+            DbErr::RecordNotUpdated => AppError::NotFound("Record not found".to_string()),
+            e => AppError::Database(e),
+        }
+    }
+}
+
+impl From<ServiceError> for AppError {
+    fn from(e: ServiceError) -> Self {
+        match e {
+            ServiceError::Db(e) => e.into(),
+            ServiceError::NotFound(msg) => AppError::NotFound(msg),
+            ServiceError::Forbidden(msg) => AppError::Forbidden(msg),
+            ServiceError::Invalid(msg) => AppError::Validation(msg),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let message = if status.is_server_error() {
+            tracing::error!(error = %self, "Request failed");
+            "Internal server error".to_string()
+        } else {
+            self.to_string()
+        };
+        match self {
+            AppError::InvalidFields(fields) => {
+                let mut body = ApiResponse::error(status, message);
+                body.data = Some(fields);
+                (status, Json(body)).into_response()
+            }
+            _ => (status, Json(ApiResponse::<()>::error(status, message))).into_response(),
+        }
+    }
+}
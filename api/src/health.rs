@@ -0,0 +1,156 @@
+//! 存活与就绪检查
+//!
+//! - `/healthz`：进程存活即返回 200，不检查任何依赖
+//! - `/readyz`：检查数据库、Redis（配置了时）和未执行的迁移（开启时），全部正常时返回 200，
+//!   否则返回 503，响应中列出每项检查的结果和耗时；收到退出信号后直接返回 503
+
+use axum::{Json, extract::State, http::StatusCode};
+use middleware::session::RedisStore;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// 单项检查的超时时间，避免依赖无响应时探针一直挂起
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 就绪检查用到的依赖，克隆后共享同一个退出标记
+#[derive(Clone)]
+pub struct Health {
+    redis: Option<RedisStore>,
+    check_migrations: bool,
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl Health {
+    pub fn new(redis: Option<RedisStore>, check_migrations: bool) -> Self {
+        Health {
+            redis,
+            check_migrations,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 标记为正在退出，之后的就绪检查都会失败
+    pub fn start_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Fail,
+}
+
+/// 单项依赖的检查结果
+#[derive(Serialize)]
+pub struct Check {
+    pub status: Status,
+    /// 检查耗时（毫秒）
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Readiness {
+    pub status: Status,
+    pub shutting_down: bool,
+    /// 按依赖名称排列，未配置的依赖不出现
+    pub checks: BTreeMap<&'static str, Check>,
+}
+
+/// 存活检查
+pub async fn healthz() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// 就绪检查，各项依赖并发检查
+pub async fn readyz(
+    State(health): State<Health>,
+    State(conn): State<DatabaseConnection>,
+) -> (StatusCode, Json<Readiness>) {
+    if health.is_shutting_down() {
+        let readiness = Readiness {
+            status: Status::Fail,
+            shutting_down: true,
+            checks: BTreeMap::new(),
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(readiness));
+    }
+
+    let (database, redis, migrations) = tokio::join!(
+        check(conn.ping()),
+        async {
+            match &health.redis {
+                Some(redis) => Some(check(redis.ping()).await),
+                None => None,
+            }
+        },
+        async {
+            if health.check_migrations {
+                Some(check(pending_migrations(&conn)).await)
+            } else {
+                None
+            }
+        },
+    );
+
+    let mut checks = BTreeMap::from([("database", database)]);
+    checks.extend(redis.map(|check| ("redis", check)));
+    checks.extend(migrations.map(|check| ("migrations", check)));
+
+    let ready = checks.values().all(|check| check.status == Status::Ok);
+    let readiness = Readiness {
+        status: if ready { Status::Ok } else { Status::Fail },
+        shutting_down: false,
+        checks,
+    };
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+async fn check<E: Display>(future: impl Future<Output = Result<(), E>>) -> Check {
+    let start = Instant::now();
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, future).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("Timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    Check {
+        status: if error.is_none() {
+            Status::Ok
+        } else {
+            Status::Fail
+        },
+        // 保留到微秒
+        latency_ms: (start.elapsed().as_secs_f64() * 1e6).round() / 1e3,
+        error,
+    }
+}
+
+/// 有未执行的迁移时返回它们的名称
+async fn pending_migrations(conn: &DatabaseConnection) -> Result<(), String> {
+    let pending = Migrator::get_pending_migrations(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&str> = pending.iter().map(|m| m.name()).collect();
+    Err(format!("Pending migrations: {}", names.join(", ")))
+}
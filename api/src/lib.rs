@@ -1,120 +1,143 @@
+pub mod auth;
 mod comments;
+pub mod config;
+pub mod error;
 mod flash;
+pub mod health;
+pub mod metrics;
+pub mod openapi;
+pub mod permission;
 mod posts;
-mod request;
-mod response;
+pub mod request;
+pub mod response;
+pub mod routes;
+mod state;
+mod tags;
+pub mod telemetry;
 mod users;
+mod v2;
+pub mod validation;
+use anyhow::{Context, anyhow};
 use axum::{
     Router,
     http::StatusCode,
-    middleware::from_fn,
-    routing::{get, get_service, post, put},
+    middleware::{from_fn, from_fn_with_state},
+    routing::{get, get_service},
 };
+use config::{Config, LogConfig, TelemetryConfig};
+use health::Health;
+use middleware::axum::AuthState;
+use middleware::jwt::JwtKeys;
+use middleware::metrics::Metrics;
+use middleware::session::{AppSessionStore, RedisStore};
 use middleware::tower::LoggingLayer;
 use migration::sea_orm::Database;
 use opentelemetry::trace::TracerProvider;
-use std::env;
-use std::sync::OnceLock;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use state::AppState;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock, OnceLock};
 use tower_cookies::CookieManagerLayer;
-use tower_http::trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse};
+use tower_http::trace::{DefaultOnRequest, DefaultOnResponse};
 use tower_http::{services::ServeDir, trace::TraceLayer};
+use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer, cookie::time::Duration};
 use tracing::Level;
 use tracing::*;
-use tracing_subscriber::{
-    Layer, filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt,
-};
-
-use uitls::dotenv;
+use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
 
 // 使用 OnceLock 来安全地存储日志 guard
 static LOG_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
 
-fn init_log() {
-    // 从环境变量读取日志配置
-    let enable_console_log = env::var("ENABLE_CONSOLE_LOG")
-        .map(|v| v.to_lowercase() == "true")
-        .unwrap_or(true);
-    let enable_file_log = env::var("ENABLE_FILE_LOG")
-        .map(|v| v.to_lowercase() == "true")
-        .unwrap_or(false);
-    let enable_opentelemetry_log = env::var("ENABLE_OPENTELEMETRY")
-        .map(|v| v.to_lowercase() == "true")
-        .unwrap_or(false);
+/// 初始化日志系统，启用 OpenTelemetry 时返回追踪提供者，退出前需要关闭
+fn init_log(
+    config: &LogConfig,
+    telemetry: &TelemetryConfig,
+) -> anyhow::Result<Option<SdkTracerProvider>> {
     let mut layers: Vec<Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync>> = Vec::new();
+    let mut tracer_provider = None;
 
     // 添加控制台日志层
-    if enable_console_log {
+    if config.console {
         let stdout_layer = tracing_subscriber::fmt::layer()
             .with_writer(std::io::stdout)
             .with_ansi(true)
-            .with_filter(LevelFilter::INFO);
+            .with_filter(config.console_level);
         layers.push(Box::new(stdout_layer));
     }
 
     // 添加文件日志层
-    if enable_file_log {
-        let file_appender = tracing_appender::rolling::daily("logs", "myapp.log");
+    if config.file {
+        let file_appender = tracing_appender::rolling::daily(&config.dir, &config.file_name);
         let (non_blocking_file, guard) = tracing_appender::non_blocking(file_appender);
 
-        // 使用 OnceLock 安全地存储 guard
-        match LOG_GUARD.set(guard) {
-            Ok(_) => {}
-            Err(e) => panic!("Failed to set LOG_GUARD: {:?}", e),
-        }
+        // 使用 OnceLock 安全地存储 guard，重复初始化时返回错误
+        LOG_GUARD
+            .set(guard)
+            .map_err(|_| anyhow!("Logging is already initialized"))?;
 
         let file_layer = tracing_subscriber::fmt::layer()
             .with_writer(non_blocking_file)
             .with_ansi(false)
-            .with_filter(LevelFilter::DEBUG);
+            .with_filter(config.file_level);
 
         layers.push(Box::new(file_layer));
     }
 
     // 添加 OpenTelemetry 追踪层
-    if enable_opentelemetry_log {
-        // 创建 OpenTelemetry 追踪层,该追踪器默认导出到 stdout
-        let exporter = opentelemetry_stdout::SpanExporter::default();
-
-        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
-            .with_simple_exporter(exporter)
-            .build();
-
+    if config.opentelemetry {
+        // 按配置通过 OTLP 批量导出，见 telemetry 模块
+        let provider = telemetry::tracer_provider(telemetry)?;
         let tracer = provider.tracer("rs-web-tracer");
 
         // 创建一个tracing层，使用配置好的tracer
         let telemetry_layer = tracing_opentelemetry::layer()
             .with_tracer(tracer)
-            .with_filter(LevelFilter::INFO);
+            .with_filter(config.level);
 
         layers.push(Box::new(telemetry_layer));
+        tracer_provider = Some(provider);
     }
     // 初始化所有日志层
-    tracing_subscriber::registry().with(layers).init();
+    tracing_subscriber::registry().with(layers).try_init()?;
+    Ok(tracer_provider)
 }
 
 pub async fn start() -> anyhow::Result<()> {
-    // 加载 .env 配置文件
-    match dotenv() {
-        Ok(_) => info!("Successfully loaded .env file"),
-        Err(e) => {
-            error!("Failed to load .env file: {}", e);
-            std::process::exit(1);
-        }
-    }
+    // 加载配置，出错时列出全部有问题的配置项
+    let config = Config::load()?;
 
     // 初始化日志系统
-    init_log();
+    let tracer_provider = init_log(&config.log, &config.telemetry)?;
+
+    // 加载 JWT 签发与校验密钥
+    let jwt_keys = Arc::new(JwtKeys::new(&config.jwt)?);
+    // 提前生成登录时比对用的占位摘要，避免第一次请求多一次哈希的耗时
+    LazyLock::force(&auth::DUMMY_PASSWORD_HASH);
 
-    // 从环境变量读取数据库和服务器配置
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
-    let host = env::var("HOST").expect("HOST is not set in .env file");
-    let port = env::var("PORT").expect("PORT is not set in .env file");
-    let server_url = format!("{host}:{port}");
+    // 会话存储：配置了 REDIS_URL 时使用 Redis，否则使用内存存储
+    let redis = match &config.session.redis_url {
+        Some(url) => Some(RedisStore::connect(url).await?),
+        None => {
+            warn!("REDIS_URL is not set, sessions are stored in memory");
+            None
+        }
+    };
+    let session_store = match redis.clone() {
+        Some(store) => AppSessionStore::Redis(store),
+        None => AppSessionStore::Memory(MemoryStore::default()),
+    };
+    let session_layer = SessionManagerLayer::new(session_store)
+        .with_secure(config.session.secure)
+        .with_expiry(Expiry::OnInactivity(Duration::seconds(config.session.ttl)));
 
     // 建立数据库连接
-    let conn = Database::connect(db_url)
+    let mut conn = Database::connect(&config.database_url)
         .await
-        .expect("Database connection failed");
+        .context("Database connection failed")?;
+
+    // Prometheus 指标，记录 HTTP 请求和数据库查询
+    let metrics = Metrics::new()?;
+    metrics::observe_queries(&mut conn, &metrics);
 
     // 新版数据库迁移 需要开启 schema-sync 和 entity-registry
     conn.get_schema_registry("entity::*").sync(&conn).await?;
@@ -133,8 +156,8 @@ pub async fn start() -> anyhow::Result<()> {
     // 配置 HTTP 请求追踪中间件
     // 使用自定义配置来获取更详细的请求日志信息
     let trace_layer = TraceLayer::new_for_http()
-        // 配置如何创建追踪 span，设置日志级别为 INFO
-        .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+        // 配置如何创建追踪 span，级别为 INFO，并关联请求头中的上游追踪
+        .make_span_with(telemetry::make_span::<axum::body::Body>)
         // 配置请求处理开始时的日志记录，设置日志级别为 INFO
         .on_request(DefaultOnRequest::new().level(Level::INFO))
         // 配置响应返回时的日志记录
@@ -145,32 +168,39 @@ pub async fn start() -> anyhow::Result<()> {
                 .latency_unit(tower_http::LatencyUnit::Millis),
         );
 
+    // 就绪检查，退出时先让它失败
+    let health = Health::new(redis, config.health.check_migrations);
+
+    // 认证中间件：会话中的登录用户每次从用户表读取角色
+    let auth_state = AuthState {
+        keys: jwt_keys.clone(),
+        users: Arc::new(auth::DbUserLoader(conn.clone())),
+    };
+
+    let state = AppState {
+        conn,
+        jwt: jwt_keys,
+        metrics: metrics.clone(),
+        health: health.clone(),
+    };
+
     let app = Router::new()
-        .route("/posts", get(posts::list).post(posts::create))
-        // .route(
-        //     "/posts/{id}",
-        //     get(posts::show).post(posts::update).delete(posts::delete),
-        // )
-        // 用户相关路由
-        .route("/users", post(users::create))
-        .route("/users/{id}", put(users::update).delete(users::delete))
-        // 用户的文章路由
-        .route("/users/{user_id}/posts", get(posts::list_by_user))
-        // 评论相关路由
-        .route(
-            "/posts/{post_id}/comments",
-            get(comments::list).post(comments::create),
-        )
+        // 各版本的接口，见 routes 模块
+        .nest(routes::V1, routes::v1().into_router())
+        .nest(routes::V2, routes::v2().into_router())
+        // OpenAPI 文档，交互式页面见 /static/docs.html
+        .route("/openapi.json", get(openapi::openapi_json))
+        // Prometheus 指标，只允许 METRICS_ALLOW 中的地址访问
         .route(
-            "/posts/{post_id}/comments/{comment_id}",
-            post(comments::update).delete(comments::delete),
+            "/metrics",
+            get(metrics::metrics).route_layer(from_fn_with_state(
+                config.metrics_allow.clone(),
+                metrics::require_allowed,
+            )),
         )
-        // 搜索路由
-        .route("/search/posts", get(posts::search))
-        // 统计路由
-        .route("/statistics", get(posts::statistics))
-        // 测试 span 路由
-        .route("/span/{id}", get(posts::show_span))
+        // 存活与就绪检查
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
         // 静态文件服务
         .nest_service(
             "/static",
@@ -185,17 +215,70 @@ pub async fn start() -> anyhow::Result<()> {
                 )
             }),
         )
-        .layer(LoggingLayer::new())
+        // 按路由统计请求数和耗时
+        .layer(metrics.layer())
+        // 在请求上下文中记录追踪 ID
+        .layer(from_fn(telemetry::record_trace_id))
         // 添加增强的追踪中间件
         .layer(trace_layer)
-        .layer(from_fn(middleware::axum::auth))
+        .layer(from_fn_with_state(auth_state, middleware::axum::auth))
+        // 添加会话中间件，认证中间件会从会话中读取登录用户
+        .layer(session_layer)
         // 添加 Cookie 管理中间件
         .layer(CookieManagerLayer::new())
-        // 注入数据库连接作为应用状态
-        .with_state(conn);
+        // 请求 ID 放在最外层，认证失败等提前返回的响应和请求 span 都能带上
+        .layer(LoggingLayer::new().trusted_proxies(config.trusted_proxies.clone()))
+        // 注入应用状态
+        .with_state(state);
 
-    let listener = tokio::net::TcpListener::bind(&server_url).await.unwrap();
-    axum::serve(listener, app).await?;
+    let addr = config.addr();
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("Failed to bind {addr}"))?;
+    // 提供对端地址，用于确定请求上下文中的客户端地址
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(health, config.health.shutdown_delay))
+    .await?;
+
+    // 导出缓冲中尚未发送的 span
+    if let Some(provider) = tracer_provider
+        && let Err(e) = provider.shutdown()
+    {
+        warn!("Failed to shut down tracer provider: {e}");
+    }
 
     Ok(())
 }
+
+/// 等待 Ctrl+C 或 SIGTERM
+///
+/// 收到后先让就绪检查失败，等待 `delay` 秒让负载均衡摘除本实例，
+/// 再停止接收新连接并等待进行中的请求完成。
+async fn shutdown_signal(health: Health, delay: u64) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    health.start_shutdown();
+    info!("Shutdown signal received, stopping in {delay}s");
+    tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+}
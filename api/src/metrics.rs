@@ -0,0 +1,57 @@
+//! Prometheus 指标接口
+//!
+//! 接口不需要登录，只允许 `METRICS_ALLOW` 中的地址访问，见 `require_allowed`。
+
+use crate::error::AppError;
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use middleware::context::{RequestContext, TrustedProxies};
+use middleware::metrics::{CONTENT_TYPE, Metrics};
+use sea_orm::{DatabaseConnection, DatabaseConnectionType};
+
+/// 数据库查询完成时记录耗时，连接建立后调用一次
+pub fn observe_queries(conn: &mut DatabaseConnection, metrics: &Metrics) {
+    let metrics = metrics.clone();
+    conn.set_metric_callback(move |info| {
+        metrics.observe_query(&info.statement.sql, info.elapsed, info.failed)
+    });
+}
+
+/// Prometheus 抓取接口，抓取时更新连接池状态
+pub async fn metrics(
+    State(metrics): State<Metrics>,
+    State(conn): State<DatabaseConnection>,
+) -> Result<impl IntoResponse, AppError> {
+    // 只有 MySQL 连接池有连接数，MockDatabase 等其他连接不更新
+    if let DatabaseConnectionType::SqlxMySqlPoolConnection(_) = &conn.inner {
+        let pool = conn.get_mysql_connection_pool();
+        metrics.set_pool(pool.size(), pool.num_idle());
+    }
+    let body = metrics
+        .render()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], body))
+}
+
+/// 客户端地址不在 `allow` 中时返回 403，客户端地址取自请求上下文，经过可信代理时取转发头中的地址
+pub async fn require_allowed(
+    State(allow): State<TrustedProxies>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let client_ip = req
+        .extensions()
+        .get::<RequestContext>()
+        .and_then(|ctx| ctx.client_ip);
+    match client_ip {
+        Some(ip) if allow.contains(ip) => next.run(req).await,
+        _ => {
+            tracing::warn!(?client_ip, "Metrics request from a disallowed address");
+            AppError::Forbidden("Permission denied".to_string()).into_response()
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! OpenAPI 文档
+//!
+//! 路径由各处理函数上的 `#[utoipa::path]` 生成，请求和响应结构由 DTO 上的
+//! `ToSchema` / `IntoParams` 生成。文档以 JSON 形式挂在 `/openapi.json`，
+//! 交互式页面为静态目录下的 `/static/docs.html`。
+//!
+//! 处理函数上的路径不含版本前缀，`api_doc` 按 `routes` 中的版本划分组合出完整路径。
+
+use crate::routes::{V1, V2};
+use crate::{auth, comments, posts, tags, users, v2};
+use axum::Json;
+use utoipa::{
+    Modify, OpenApi,
+    openapi::{
+        Deprecated, Paths,
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "rs-web API"),
+    paths(
+        auth::login,
+        auth::refresh,
+        auth::logout,
+        auth::session_login,
+        auth::session_logout,
+        posts::list,
+        posts::create,
+        posts::show,
+        posts::update,
+        posts::delete,
+        posts::list_by_user,
+        posts::search,
+        posts::statistics,
+        tags::list,
+        tags::posts,
+        tags::attach,
+        tags::detach,
+        users::list,
+        users::create,
+        users::update,
+        users::delete,
+        users::update_role,
+        comments::list,
+        comments::replies,
+        comments::create,
+        comments::update,
+        comments::delete,
+    ),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "auth", description = "登录、刷新令牌与会话"),
+        (name = "posts", description = "文章"),
+        (name = "comments", description = "评论与回复"),
+        (name = "tags", description = "标签"),
+        (name = "users", description = "用户"),
+    )
+)]
+pub struct ApiDoc;
+
+/// v2 中覆盖的接口
+#[derive(OpenApi)]
+#[openapi(paths(v2::show_post))]
+struct V2Doc;
+
+/// 注册 Bearer 令牌认证方式，需要登录的接口通过 `security(("bearer" = []))` 引用
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// 组合各版本的文档，与 `routes::v1` / `routes::v2` 保持一致
+pub fn api_doc() -> utoipa::openapi::OpenApi {
+    let mut v1 = ApiDoc::openapi();
+    if let Some(operation) = v1
+        .paths
+        .paths
+        .get_mut("/posts/{id}")
+        .and_then(|item| item.get.as_mut())
+    {
+        operation.deprecated = Some(Deprecated::True);
+    }
+    // 合并时保留已有的操作，先放入覆盖的接口
+    let v2 = V2Doc::openapi().merge_from(ApiDoc::openapi());
+
+    let mut doc = ApiDoc::openapi();
+    doc.paths = Paths::new();
+    doc.nest(V1, v1).nest(V2, v2)
+}
+
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(api_doc())
+}
+
+/// 响应的文档结构
+///
+/// 实体模型、业务层和中间件中的类型在开启 `utoipa` 特性后派生 `ToSchema`，这里直接引用；
+/// 其余结构描述的是组合后的响应，只用于生成文档，不会被构造。
+#[allow(dead_code)]
+pub mod schema {
+    pub use crate::validation::FieldError;
+    pub use entity::comment::Model as Comment;
+    pub use entity::post::Model as Post;
+    pub use entity::profile::Model as Profile;
+    pub use entity::tag::Model as Tag;
+    pub use entity::user::Role;
+    pub use middleware::axum::UserInfo;
+    pub use service::{Author, CommentNode, CommentWithAuthor};
+
+    use serde::Serialize;
+    use service::{PostRelations, UserRelations};
+    use utoipa::ToSchema;
+
+    /// 错误响应，`code` 与 HTTP 状态码一致，字段校验失败时 `data` 为字段错误列表
+    #[derive(Serialize, ToSchema)]
+    pub struct ErrorResponse {
+        pub code: u16,
+        pub message: String,
+        #[schema(value_type = Option<Vec<FieldError>>)]
+        pub data: Option<serde_json::Value>,
+        /// 请求 ID，与响应头 `X-Request-ID` 相同
+        pub request_id: Option<String>,
+    }
+
+    /// 不带数据的成功响应
+    #[derive(Serialize, ToSchema)]
+    pub struct MessageResponse {
+        pub code: u16,
+        pub message: String,
+        #[schema(value_type = Option<Object>)]
+        pub data: Option<serde_json::Value>,
+    }
+
+    /// 文章及 `include` 指定嵌入的关联，见 `posts::PostView`
+    ///
+    /// 指定 `fields` 时文章本身只包含选择的字段，未嵌入的关联不出现。
+    #[derive(Serialize, ToSchema)]
+    pub struct PostView {
+        #[serde(flatten)]
+        pub post: Post,
+        #[serde(flatten)]
+        pub relations: PostRelations,
+    }
+
+    /// 用户及 `include` 指定嵌入的关联，见 `users::UserWithRelations`
+    ///
+    /// 指定 `fields` 时用户本身只包含选择的字段，未嵌入的关联不出现。
+    #[derive(Serialize, ToSchema)]
+    pub struct UserWithRelations {
+        #[serde(flatten)]
+        pub user: crate::users::UserView,
+        #[serde(flatten)]
+        pub relations: UserRelations,
+    }
+
+    /// 文章及其全部评论，即 `post::ModelEx` 加载评论后的结果
+    #[derive(Serialize, ToSchema)]
+    pub struct PostDetail {
+        #[serde(flatten)]
+        pub post: Post,
+        pub comments: Vec<Comment>,
+    }
+}
@@ -0,0 +1,70 @@
+//! 基于角色的访问控制
+//!
+//! 路由通过 `route_layer` 声明所需权限：
+//!
+//! ```ignore
+//! delete(users::delete)
+//!     .route_layer(from_fn_with_state(Permission::ManageUsers, require_permission))
+//! ```
+//!
+//! 角色取自认证中间件写入的当前用户：会话登录的用户每次请求从用户表读取，
+//! 修改角色后立即生效；Bearer 令牌中的角色在令牌过期（默认 15 分钟）前有效，
+//! 刷新令牌时重新读取。
+
+use crate::error::AppError;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use entity::user::{Permission, Role};
+use middleware::axum::{RequestContext, UserInfo};
+use service::Actor;
+
+/// 解析用户角色，无法识别的角色按普通用户处理
+pub fn role_of(user: &UserInfo) -> Role {
+    user.role.parse().unwrap_or_default()
+}
+
+/// 将当前用户转换为业务层的操作者，带上请求 ID 和客户端地址用于审计
+pub fn actor(user: &UserInfo, ctx: &RequestContext) -> Actor {
+    Actor {
+        user_id: user.user_id as i32,
+        role: role_of(user),
+        request_id: Some(ctx.request_id.clone()),
+        client_ip: ctx.client_ip.map(|ip| ip.to_string()),
+    }
+}
+
+/// 检查当前用户是否拥有权限，未登录返回 401，权限不足返回 403
+pub async fn require_permission(
+    State(permission): State<Permission>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let user = req
+        .extensions()
+        .get::<RequestContext>()
+        .and_then(|ctx| ctx.user_info.as_ref());
+
+    let denied = match user {
+        None => Some(AppError::Unauthorized(
+            "Authentication required".to_string(),
+        )),
+        Some(user) if !role_of(user).has(permission) => {
+            tracing::warn!(
+                user_id = user.user_id,
+                role = %user.role,
+                ?permission,
+                "Permission denied"
+            );
+            Some(AppError::Forbidden("Permission denied".to_string()))
+        }
+        Some(_) => None,
+    };
+
+    match denied {
+        Some(e) => e.into_response(),
+        None => next.run(req).await,
+    }
+}
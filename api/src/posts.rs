@@ -1,62 +1,254 @@
-use super::request::PageParams;
+use super::error::{AppError, AppResult, PageResult};
+use super::openapi::schema;
+use super::permission::actor;
+use super::request::{ListParams, Listing, PageParams, ViewParams};
 use super::response::ApiResponse;
 use super::response::PageRes;
+use super::validation::Valid;
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{OriginalUri, Path, Query, State},
     response::Json,
 };
 use entity::post;
-use sea_orm::DatabaseConnection;
+use middleware::axum::{RequestContext, UserInfo};
+use sea_orm::{DatabaseConnection, ItemsAndPagesNumber, TryIntoModel};
 use serde::{Deserialize, Serialize};
-use service::{Mutation as MutationCore, Query as QueryCore};
+use service::{Embedded, Mutation as MutationCore, PostInclude, PostRelations, Query as QueryCore};
 use tracing::info_span;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
+/// 文章接口可以通过 `fields` 选择的字段
+const POST_FIELDS: &[&str] = &["id", "user_id", "title", "body"];
+
+/// 按 `fields` 和 `include` 生成的文章
+pub type PostView = Embedded<serde_json::Value, PostRelations>;
+
+/// 为一组文章嵌入关联并去掉未选择的字段
+pub(crate) async fn post_views(
+    conn: &DatabaseConnection,
+    posts: Vec<post::Model>,
+    view: &ViewParams,
+) -> Result<Vec<PostView>, AppError> {
+    let fields = view.fields(POST_FIELDS)?;
+    let include = view.include(PostInclude::parse)?;
+    QueryCore::embed_posts(conn, posts, include)
+        .await?
+        .into_iter()
+        .map(|post| {
+            Ok(Embedded {
+                model: fields.select(post.model)?,
+                relations: post.relations,
+            })
+        })
+        .collect()
+}
+
+/// 对一页文章执行 `post_views`，分页信息不变
+pub(crate) async fn post_page(
+    conn: &DatabaseConnection,
+    mut page: PageRes<Vec<post::Model>>,
+    view: &ViewParams,
+) -> Result<PageRes<Vec<PostView>>, AppError> {
+    let posts = std::mem::take(&mut page.data);
+    Ok(page.with_data(post_views(conn, posts, view).await?))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagFilter {
+    /// 只返回带有该标签（slug）的文章
+    pub tag: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/posts",
+    tag = "posts",
+    params(PageParams, TagFilter, ListParams, ViewParams),
+    responses(
+        (status = 200, body = ApiResponse<PageRes<Vec<schema::PostView>>>),
+        (status = 400, description = "Invalid filter or sort", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn list(
     State(conn): State<DatabaseConnection>,
-    Query(page_params): Query<PageParams>,
-) -> Result<Json<ApiResponse<PageRes<Vec<post::Model>>>>, (StatusCode, Json<ApiResponse<()>>)> {
-    let page = page_params.page.unwrap_or(1);
-    let size = page_params.size.unwrap_or(10);
-    match QueryCore::find_posts_in_page(&conn, page, size).await {
-        Ok(posts) => Ok(Json(ApiResponse::success_with_data(PageRes {
-            data: posts.0,
-            total: posts.1,
-        }))),
-        Err(e) => {
-            let error_response =
-                ApiResponse::<()>::error_with_message(format!("Database error: {}", e));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
+    OriginalUri(uri): OriginalUri,
+    Valid(Query(page_params)): Valid<Query<PageParams>>,
+    Query(filter): Query<TagFilter>,
+    listing: Listing<post::Entity>,
+    Query(view): Query<ViewParams>,
+) -> PageResult<PostView> {
+    let page = page_params.page();
+    let size = page_params.size(10);
+    let cursor = listing.check_cursor(page_params.cursor()?)?;
+    let list = &listing.0;
+
+    // 按标签过滤
+    let tag = match filter.tag.as_deref() {
+        Some(slug) => Some(QueryCore::find_tag_by_slug(&conn, slug).await?),
+        None => None,
+    };
+
+    let posts = match (tag, cursor) {
+        // 标签不存在时返回空列表
+        (Some(None), _) => {
+            let totals = ItemsAndPagesNumber {
+                number_of_items: 0,
+                number_of_pages: 0,
+            };
+            listing.from_page(Vec::new(), page, size, totals, |p: &post::Model| p.id)
         }
-    }
+        (Some(Some(tag)), Some(cursor)) => PageRes::from_cursor(
+            QueryCore::find_posts_by_tag_with_cursor(&conn, &tag, list, Some(cursor), size).await?,
+            size,
+        ),
+        (Some(Some(tag)), None) => {
+            let (posts, totals) =
+                QueryCore::find_posts_by_tag_in_page(&conn, &tag, list, page, size).await?;
+            listing.from_page(posts, page, size, totals, |p| p.id)
+        }
+        (None, Some(cursor)) => PageRes::from_cursor(
+            QueryCore::find_posts_with_cursor(&conn, list, Some(cursor), size).await?,
+            size,
+        ),
+        (None, None) => {
+            let (posts, totals) = QueryCore::find_posts_in_page(&conn, list, page, size).await?;
+            listing.from_page(posts, page, size, totals, |p| p.id)
+        }
+    };
+
+    Ok(post_page(&conn, posts, &view).await?.with_links(&uri))
 }
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct PostInput {
+    #[validate(length(min = 1, max = 200))]
+    pub title: String,
+    #[validate(length(min = 1, max = 20000))]
+    pub body: String,
+}
+
+// 创建文章，作者为当前登录用户
+#[utoipa::path(
+    post,
+    path = "/posts",
+    tag = "posts",
+    request_body = PostInput,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<schema::Post>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn create(
-    State(_conn): State<DatabaseConnection>,
-    Json(_user): Json<post::Model>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, Json<ApiResponse<()>>)> {
-    todo!()
+    State(conn): State<DatabaseConnection>,
+    user: UserInfo,
+    Valid(Json(input)): Valid<Json<PostInput>>,
+) -> AppResult<post::Model> {
+    let form_data = post::Model {
+        id: 0,
+        user_id: user.user_id as i32,
+        title: input.title,
+        body: input.body,
+    };
+
+    let post = MutationCore::create_post(&conn, form_data)
+        .await?
+        .try_into_model()?;
+
+    Ok(Json(ApiResponse::success_with_data(post)))
 }
 
+// 获取文章及其全部评论
+#[utoipa::path(
+    get,
+    path = "/posts/{id}",
+    tag = "posts",
+    params(("id" = i32, Path, description = "文章 ID")),
+    responses(
+        (status = 200, body = ApiResponse<schema::PostDetail>),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+    )
+)]
+pub async fn show(
+    State(conn): State<DatabaseConnection>,
+    Path(id): Path<i32>,
+) -> AppResult<post::ModelEx> {
+    let post = QueryCore::find_post_by_id(&conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    Ok(Json(ApiResponse::success_with_data(post)))
+}
+
+// 修改文章，只有作者或管理员可以修改
+#[utoipa::path(
+    post,
+    path = "/posts/{id}",
+    tag = "posts",
+    params(("id" = i32, Path, description = "文章 ID")),
+    request_body = PostInput,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<schema::Post>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn update(
+    State(conn): State<DatabaseConnection>,
+    user: UserInfo,
+    ctx: RequestContext,
+    Path(id): Path<i32>,
+    Valid(Json(input)): Valid<Json<PostInput>>,
+) -> AppResult<post::Model> {
+    let form_data = post::Model {
+        id,
+        user_id: user.user_id as i32,
+        title: input.title,
+        body: input.body,
+    };
+
+    let post = MutationCore::update_post_by_id(&conn, &actor(&user, &ctx), id, form_data).await?;
+
+    Ok(Json(ApiResponse::success_with_data(post)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/posts/{id}",
+    tag = "posts",
+    params(("id" = i32, Path, description = "文章 ID")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = schema::MessageResponse),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+    )
+)]
 pub async fn delete(
     State(conn): State<DatabaseConnection>,
+    user: UserInfo,
+    ctx: RequestContext,
     Path(id): Path<i32>,
-) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
-    match MutationCore::delete_post(&conn, id).await {
-        Ok(_) => Ok(Json(ApiResponse::<()>::success_with_message(
-            "Post deleted successfully".to_string(),
-        ))),
-        Err(e) => {
-            let error_response =
-                ApiResponse::<()>::error_with_message(format!("Database error: {}", e));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-        }
-    }
+) -> AppResult<()> {
+    MutationCore::delete_post(&conn, &actor(&user, &ctx), id).await?;
+
+    Ok(Json(ApiResponse::<()>::success_with_message(
+        "Post deleted successfully".to_string(),
+    )))
 }
 
 pub async fn show_span(
     State(conn): State<DatabaseConnection>,
     Path(id): Path<i32>,
-) -> Result<Json<ApiResponse<i32>>, (StatusCode, Json<ApiResponse<i32>>)> {
+) -> AppResult<i32> {
     // 创建一个 span 来追踪获取单个文章的操作
     let span = info_span!("show_post", post_id = id);
     let _enter = span.enter();
@@ -81,100 +273,121 @@ pub async fn show_span(
 }
 
 // 获取指定用户的所有文章
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/posts",
+    tag = "posts",
+    params(("user_id" = i32, Path, description = "用户 ID"), PageParams, ViewParams),
+    responses(
+        (status = 200, body = ApiResponse<PageRes<Vec<schema::PostView>>>),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn list_by_user(
     State(conn): State<DatabaseConnection>,
+    OriginalUri(uri): OriginalUri,
     Path(user_id): Path<i32>,
-) -> Result<Json<ApiResponse<Vec<post::Model>>>, (StatusCode, Json<ApiResponse<Vec<post::Model>>>)>
-{
+    Valid(Query(page_params)): Valid<Query<PageParams>>,
+    Query(view): Query<ViewParams>,
+) -> PageResult<PostView> {
+    let page = page_params.page();
+    let size = page_params.size(10);
+    let cursor = page_params.cursor()?;
+
     // 首先检查用户是否存在
-    match QueryCore::find_user_by_id(&conn, user_id).await {
-        Ok(Some(_user)) => {
-            // 获取用户的文章
-            match QueryCore::find_posts_by_user_id(&conn, user_id).await {
-                Ok(posts) => Ok(Json(ApiResponse::success_with_data(posts))),
-                Err(e) => {
-                    let error_response = ApiResponse::<Vec<post::Model>>::error_with_message(
-                        format!("Database error: {}", e),
-                    );
-                    Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                }
-            }
-        }
-        Ok(None) => {
-            let error_response =
-                ApiResponse::<Vec<post::Model>>::error_with_message("User not found".to_string());
-            Err((StatusCode::NOT_FOUND, Json(error_response)))
-        }
-        Err(e) => {
-            let error_response = ApiResponse::<Vec<post::Model>>::error_with_message(format!(
-                "Database error: {}",
-                e
-            ));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
+    QueryCore::find_user_by_id(&conn, user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    // 获取用户的文章
+    let posts = match cursor {
+        Some(cursor) => PageRes::from_cursor(
+            QueryCore::find_posts_by_user_id_with_cursor(&conn, user_id, Some(cursor), size)
+                .await?,
+            size,
+        ),
+        None => {
+            let (posts, totals) =
+                QueryCore::find_posts_by_user_id_in_page(&conn, user_id, page, size).await?;
+            PageRes::from_page(posts, page, size, totals, |p| p.id)
         }
-    }
+    };
+    Ok(post_page(&conn, posts, &view).await?.with_links(&uri))
 }
 
 // 搜索文章
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SearchParams {
+    /// 搜索关键字，匹配标题和正文
+    #[validate(length(min = 1, max = 100))]
     pub q: String,
-    #[serde(flatten)]
-    pub pagination: PageParams,
 }
 
+#[utoipa::path(
+    get,
+    path = "/search/posts",
+    tag = "posts",
+    params(SearchParams, PageParams, ViewParams),
+    responses(
+        (status = 200, body = ApiResponse<PageRes<Vec<schema::PostView>>>),
+        (status = 400, description = "Invalid request", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn search(
     State(conn): State<DatabaseConnection>,
-    Query(params): Query<SearchParams>,
-) -> Result<Json<ApiResponse<Vec<post::Model>>>, (StatusCode, Json<ApiResponse<Vec<post::Model>>>)>
-{
-    let page = params.pagination.page.unwrap_or(1);
-    let posts_per_page = params.pagination.size.unwrap_or(5);
+    OriginalUri(uri): OriginalUri,
+    Valid(Query(params)): Valid<Query<SearchParams>>,
+    Valid(Query(page_params)): Valid<Query<PageParams>>,
+    Query(view): Query<ViewParams>,
+) -> PageResult<PostView> {
+    let page = page_params.page();
+    let posts_per_page = page_params.size(5);
     let keyword = params.q.trim();
 
     if keyword.is_empty() {
-        let error_response = ApiResponse::<Vec<post::Model>>::error_with_message(
+        return Err(AppError::Validation(
             "Search keyword is required".to_string(),
-        );
-        return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+        ));
     }
 
-    match QueryCore::search_posts(&conn, keyword, page, posts_per_page).await {
-        Ok((posts, _num_pages)) => Ok(Json(ApiResponse::success_with_data(posts))),
-        Err(e) => {
-            let error_response = ApiResponse::<Vec<post::Model>>::error_with_message(format!(
-                "Database error: {}",
-                e
-            ));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
+    let posts = match page_params.cursor()? {
+        Some(cursor) => PageRes::from_cursor(
+            QueryCore::search_posts_with_cursor(&conn, keyword, Some(cursor), posts_per_page)
+                .await?,
+            posts_per_page,
+        ),
+        None => {
+            let (posts, totals) =
+                QueryCore::search_posts(&conn, keyword, page, posts_per_page).await?;
+            PageRes::from_page(posts, page, posts_per_page, totals, |p| p.id)
         }
-    }
+    };
+    Ok(post_page(&conn, posts, &view).await?.with_links(&uri))
 }
 
 // 统计信息
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Statistics {
     pub total_posts: u64,
     pub total_users: u64,
     pub total_comments: u64,
 }
 
-pub async fn statistics(
-    State(conn): State<DatabaseConnection>,
-) -> Result<Json<ApiResponse<Statistics>>, (StatusCode, Json<ApiResponse<Statistics>>)> {
-    match QueryCore::get_statistics(&conn).await {
-        Ok((total_posts, total_users, total_comments)) => {
-            let stats = Statistics {
-                total_posts,
-                total_users,
-                total_comments,
-            };
-            Ok(Json(ApiResponse::success_with_data(stats)))
-        }
-        Err(e) => {
-            let error_response =
-                ApiResponse::<Statistics>::error_with_message(format!("Database error: {}", e));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-        }
-    }
+#[utoipa::path(
+    get,
+    path = "/statistics",
+    tag = "posts",
+    responses((status = 200, body = ApiResponse<Statistics>))
+)]
+pub async fn statistics(State(conn): State<DatabaseConnection>) -> AppResult<Statistics> {
+    let (total_posts, total_users, total_comments) = QueryCore::get_statistics(&conn).await?;
+    let stats = Statistics {
+        total_posts,
+        total_users,
+        total_comments,
+    };
+    Ok(Json(ApiResponse::success_with_data(stats)))
 }
@@ -1,7 +1,195 @@
-use serde::Deserialize;
+use crate::error::AppError;
+use crate::response::PageRes;
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sea_orm::ItemsAndPagesNumber;
+use serde::{Deserialize, Serialize};
+use service::{Cursor, Filterable, ListQuery, ServiceError};
+use std::collections::HashMap;
+use utoipa::IntoParams;
+use validator::Validate;
 
-#[derive(Deserialize)]
+/// 每页最多返回的记录数
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PageParams {
+    /// 页码，从 1 开始
+    #[validate(range(min = 1))]
+    #[param(minimum = 1)]
     pub page: Option<u64>,
+    /// 每页记录数
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE))]
+    #[param(minimum = 1, maximum = 100)]
     pub size: Option<u64>,
+    /// 取该游标之后的一页，值为上一次响应中的 `next_cursor`，指定后忽略 `page`。
+    /// 游标分页按主键排序，不能与 `sort` 同时使用
+    pub after: Option<String>,
+    /// 取该游标之前的一页，值为上一次响应中的 `prev_cursor`，指定后忽略 `page`
+    pub before: Option<String>,
+}
+
+impl PageParams {
+    /// 页码，从 1 开始
+    pub fn page(&self) -> u64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    /// 每页记录数，未指定时使用 `default`，并限制在 `1..=MAX_PAGE_SIZE` 内
+    pub fn size(&self, default: u64) -> u64 {
+        self.size.unwrap_or(default).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// 游标分页的位置，`after` 和 `before` 都未指定时按页码分页
+    pub fn cursor(&self) -> Result<Option<Cursor>, AppError> {
+        let decode = |token: &str| {
+            decode_cursor(token).ok_or_else(|| AppError::Validation("Invalid cursor".to_string()))
+        };
+        match (&self.after, &self.before) {
+            (Some(_), Some(_)) => Err(AppError::Validation(
+                "Only one of after and before can be given".to_string(),
+            )),
+            (Some(token), None) => decode(token).map(|id| Some(Cursor::After(id))),
+            (None, Some(token)) => decode(token).map(|id| Some(Cursor::Before(id))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// 生成游标，内容为记录的主键，客户端只应原样传回
+pub fn encode_cursor(id: i32) -> String {
+    URL_SAFE_NO_PAD.encode(format!("id:{id}"))
+}
+
+fn decode_cursor(token: &str) -> Option<i32> {
+    let bytes = URL_SAFE_NO_PAD.decode(token).ok()?;
+    std::str::from_utf8(&bytes)
+        .ok()?
+        .strip_prefix("id:")?
+        .parse()
+        .ok()
+}
+
+/// 从查询参数中解析过滤与排序条件，语法见 `service::ListQuery`
+pub struct Listing<E: Filterable>(pub ListQuery<E>);
+
+impl<S, E> FromRequestParts<S> for Listing<E>
+where
+    S: Send + Sync,
+    E: Filterable,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(pairs) = Query::<Vec<(String, String)>>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::Validation(e.to_string()))?;
+        let list = ListQuery::parse(pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())))?;
+        Ok(Listing(list))
+    }
+}
+
+impl<E: Filterable> Listing<E> {
+    /// 游标分页固定按主键排序，不能与 `sort` 同时使用，
+    /// 所以指定了 `sort` 的页码分页也不给出游标，见 `from_page`
+    pub fn check_cursor(&self, cursor: Option<Cursor>) -> Result<Option<Cursor>, AppError> {
+        if cursor.is_some() && self.0.is_sorted() {
+            return Err(AppError::Validation(
+                "sort cannot be used with after or before".to_string(),
+            ));
+        }
+        Ok(cursor)
+    }
+
+    /// 页码分页的结果，指定了 `sort` 时不给出游标，其余同 `PageRes::from_page`
+    pub fn from_page<T>(
+        &self,
+        data: Vec<T>,
+        page: u64,
+        size: u64,
+        totals: ItemsAndPagesNumber,
+        key: impl Fn(&T) -> i32,
+    ) -> PageRes<Vec<T>> {
+        let mut res = PageRes::from_page(data, page, size, totals, key);
+        if self.0.is_sorted() {
+            res.next_cursor = None;
+            res.prev_cursor = None;
+        }
+        res
+    }
+}
+
+/// 过滤与排序参数，只用于生成文档，实际由 `Listing` 解析
+#[allow(dead_code)]
+#[derive(IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListParams {
+    /// 逗号分隔的排序字段，`-` 前缀表示降序，例如 `-id,title`
+    pub sort: Option<String>,
+    /// 过滤条件，`filter[user_id]=3` 或 `filter[title][contains]=rust`，
+    /// 运算符为 eq、ne、gt、gte、lt、lte、contains（只用于文本字段）、in、null
+    #[param(style = DeepObject, explode, value_type = Option<Object>)]
+    pub filter: Option<HashMap<String, String>>,
+}
+
+/// 稀疏字段与关联嵌入参数
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ViewParams {
+    /// 逗号分隔的字段名，只返回这些字段，不影响 `include` 嵌入的关联，例如 `id,title`
+    pub fields: Option<String>,
+    /// 逗号分隔的要嵌入的关联，文章为 author、comments、comment_count、tags，
+    /// 用户为 profile、posts
+    pub include: Option<String>,
+}
+
+impl ViewParams {
+    /// 解析 `fields`，字段必须在 `allowed` 内
+    pub fn fields(&self, allowed: &[&str]) -> Result<Fields, AppError> {
+        let names = self
+            .fields
+            .iter()
+            .flat_map(|raw| raw.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                if allowed.contains(&name) {
+                    Ok(name.to_owned())
+                } else {
+                    Err(AppError::Validation(format!("Unknown field {name}")))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Fields(Some(names).filter(|names| !names.is_empty())))
+    }
+
+    /// 用对应实体的解析函数解析 `include`，未指定时不嵌入任何关联
+    pub fn include<I: Default>(
+        &self,
+        parse: impl FnOnce(&str) -> Result<I, ServiceError>,
+    ) -> Result<I, AppError> {
+        match self.include.as_deref() {
+            Some(raw) => Ok(parse(raw)?),
+            None => Ok(I::default()),
+        }
+    }
+}
+
+/// 响应中保留的字段，未指定 `fields` 时保留全部字段
+pub struct Fields(Option<Vec<String>>);
+
+impl Fields {
+    /// 序列化记录并去掉未选择的字段
+    pub fn select<T: Serialize>(&self, model: T) -> Result<serde_json::Value, AppError> {
+        let mut value = serde_json::to_value(model)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize response: {e}")))?;
+        if let (Some(names), Some(object)) = (&self.0, value.as_object_mut()) {
+            object.retain(|key, _| names.contains(key));
+        }
+        Ok(value)
+    }
 }
@@ -1,11 +1,24 @@
+use crate::request::encode_cursor;
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use middleware::tower::current_request_id;
+use sea_orm::ItemsAndPagesNumber;
 use serde::{Deserialize, Serialize};
+use service::CursorPage;
+use utoipa::ToSchema;
 
 /// 统一API响应结构
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub code: u16,
     pub message: String,
     pub data: Option<T>,
+    /// 请求 ID，只在错误响应中返回，便于按 ID 查找日志
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -15,6 +28,7 @@ impl<T> ApiResponse<T> {
             code: 200,
             message: "Success".to_string(),
             data: Some(data),
+            request_id: None,
         }
     }
 
@@ -23,21 +37,177 @@ impl<T> ApiResponse<T> {
             code: 200,
             message,
             data: None,
+            request_id: None,
         }
     }
 
-    /// 创建错误响应
-    pub fn error_with_message(message: String) -> ApiResponse<T> {
+    /// 创建错误响应，`code` 与 HTTP 状态码一致
+    pub fn error(status: StatusCode, message: String) -> ApiResponse<T> {
         ApiResponse {
-            code: 500,
+            code: status.as_u16(),
             message,
             data: None,
+            request_id: current_request_id(),
         }
     }
 }
 /// 统一分页统一响应
-#[derive(Serialize, Deserialize)]
+///
+/// 两种分页方式都会给出前后页的游标，分别作为 `after` / `before` 参数传入，
+/// 页码分页指定了 `sort` 时没有游标。
+/// 游标分页不统计总数，`page`、`total_items` 和 `total_pages` 为空。
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct PageRes<T> {
     pub data: T,
-    pub total: u64,
+    /// 当前页码，从 1 开始
+    pub page: Option<u64>,
+    /// 每页记录数
+    pub size: u64,
+    /// 总记录数
+    pub total_items: Option<u64>,
+    /// 总页数
+    pub total_pages: Option<u64>,
+    /// 是否还有下一页
+    pub has_next: bool,
+    /// 下一页的游标，没有更多数据时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// 上一页的游标，已是第一页时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
+}
+
+impl<T> PageRes<T> {
+    /// 替换数据，分页信息不变
+    pub fn with_data<U>(self, data: U) -> PageRes<U> {
+        PageRes {
+            data,
+            page: self.page,
+            size: self.size,
+            total_items: self.total_items,
+            total_pages: self.total_pages,
+            has_next: self.has_next,
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+        }
+    }
+}
+
+impl<T> PageRes<Vec<T>> {
+    /// 页码分页的结果，`key` 取记录的主键，首尾记录作为前后页的游标
+    pub fn from_page(
+        data: Vec<T>,
+        page: u64,
+        size: u64,
+        totals: ItemsAndPagesNumber,
+        key: impl Fn(&T) -> i32,
+    ) -> Self {
+        let has_next = page < totals.number_of_pages;
+        let next_cursor = data
+            .last()
+            .filter(|_| has_next)
+            .map(|item| encode_cursor(key(item)));
+        let prev_cursor = data
+            .first()
+            .filter(|_| page > 1)
+            .map(|item| encode_cursor(key(item)));
+        PageRes {
+            data,
+            page: Some(page),
+            size,
+            total_items: Some(totals.number_of_items),
+            total_pages: Some(totals.number_of_pages),
+            has_next,
+            next_cursor,
+            prev_cursor,
+        }
+    }
+
+    /// 游标分页的结果
+    pub fn from_cursor(page: CursorPage<T>, size: u64) -> Self {
+        PageRes {
+            data: page.items,
+            page: None,
+            size,
+            total_items: None,
+            total_pages: None,
+            has_next: page.next.is_some(),
+            next_cursor: page.next.map(encode_cursor),
+            prev_cursor: page.prev.map(encode_cursor),
+        }
+    }
+
+    /// 根据请求地址生成 RFC 8288 的 `Link` 头
+    ///
+    /// 页码分页给出 first、prev、next、last，游标分页给出 first、prev、next。
+    /// 请求中的其他查询参数原样保留。
+    pub fn with_links(self, uri: &Uri) -> PageResponse<T> {
+        let mut links = vec![page_link(uri, None, "first")];
+        match (self.page, self.total_pages) {
+            (Some(page), Some(total_pages)) => {
+                if page > 1 {
+                    let prev = (page - 1).min(total_pages.max(1)).to_string();
+                    links.push(page_link(uri, Some(("page", &prev)), "prev"));
+                }
+                if self.has_next {
+                    let next = (page + 1).to_string();
+                    links.push(page_link(uri, Some(("page", &next)), "next"));
+                }
+                let last = total_pages.max(1).to_string();
+                links.push(page_link(uri, Some(("page", &last)), "last"));
+            }
+            _ => {
+                if let Some(cursor) = &self.prev_cursor {
+                    links.push(page_link(uri, Some(("before", cursor)), "prev"));
+                }
+                if let Some(cursor) = &self.next_cursor {
+                    links.push(page_link(uri, Some(("after", cursor)), "next"));
+                }
+            }
+        }
+
+        PageResponse {
+            link: HeaderValue::from_str(&links.join(", ")).ok(),
+            body: self,
+        }
+    }
+}
+
+/// 将请求地址中的分页参数替换为 `param`
+fn page_link(uri: &Uri, param: Option<(&str, &str)>, rel: &str) -> String {
+    let mut query: Vec<String> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !pair.is_empty() && !matches!(key, "page" | "after" | "before")
+        })
+        .map(str::to_owned)
+        .collect();
+    if let Some((key, value)) = param {
+        query.push(format!("{key}={value}"));
+    }
+
+    if query.is_empty() {
+        format!("<{}>; rel=\"{rel}\"", uri.path())
+    } else {
+        format!("<{}?{}>; rel=\"{rel}\"", uri.path(), query.join("&"))
+    }
+}
+
+/// 分页接口的响应，在 `ApiResponse` 之外带上 `Link` 头
+pub struct PageResponse<T> {
+    body: PageRes<Vec<T>>,
+    link: Option<HeaderValue>,
+}
+
+impl<T: Serialize> IntoResponse for PageResponse<T> {
+    fn into_response(self) -> Response {
+        let mut response = Json(ApiResponse::success_with_data(self.body)).into_response();
+        if let Some(link) = self.link {
+            response.headers_mut().append(header::LINK, link);
+        }
+        response
+    }
 }
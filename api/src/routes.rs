@@ -0,0 +1,214 @@
+//! 按版本组织的路由表
+//!
+//! 每个版本都是一张以（路径，方法）为键的路由表，挂载在 `/api/{version}` 下。
+//! 新版本从公共路由表开始，用 `route` 覆盖响应结构有变化的接口，
+//! 其余接口沿用原来的处理函数；旧版本中被覆盖的接口用 `deprecate` 标记弃用，
+//! 响应会带上 `Deprecation` / `Sunset` 头和指向新版本的 `Link`。
+
+use crate::permission::require_permission;
+use crate::state::AppState;
+use crate::{auth, comments, posts, tags, users, v2};
+use axum::{
+    Router,
+    http::Method,
+    middleware::from_fn_with_state,
+    routing::{MethodRouter, delete, get, post, put},
+};
+use chrono::{TimeZone, Utc};
+use entity::user::Permission;
+use middleware::deprecation::{Deprecation, deprecated};
+use std::collections::BTreeMap;
+
+pub const V1: &str = "/api/v1";
+pub const V2: &str = "/api/v2";
+
+/// 一个版本的路由表
+#[derive(Default)]
+pub struct ApiRoutes {
+    routes: BTreeMap<(&'static str, &'static str), MethodRouter<AppState>>,
+}
+
+impl ApiRoutes {
+    /// 添加路由，（路径，方法）已存在时替换原来的处理函数
+    ///
+    /// `method_router` 只能处理 `method` 一种方法，同一路径的多个方法在
+    /// `into_router` 时合并。
+    pub fn route(
+        mut self,
+        path: &'static str,
+        method: Method,
+        method_router: MethodRouter<AppState>,
+    ) -> Self {
+        self.routes
+            .insert((path, method_key(&method)), method_router);
+        self
+    }
+
+    /// 将接口标记为弃用，接口不存在时 panic
+    pub fn deprecate(
+        mut self,
+        path: &'static str,
+        method: Method,
+        deprecation: Deprecation,
+    ) -> Self {
+        let method_router = self
+            .routes
+            .remove(&(path, method_key(&method)))
+            .unwrap_or_else(|| panic!("no route for {method} {path}"));
+        self.routes.insert(
+            (path, method_key(&method)),
+            method_router.route_layer(from_fn_with_state(deprecation, deprecated)),
+        );
+        self
+    }
+
+    /// 全部（路径，方法），用于核对文档是否覆盖了每个接口
+    pub fn endpoints(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.routes.keys().copied()
+    }
+
+    pub fn into_router(self) -> Router<AppState> {
+        let mut paths: BTreeMap<&'static str, MethodRouter<AppState>> = BTreeMap::new();
+        for ((path, _), method_router) in self.routes {
+            let merged = match paths.remove(path) {
+                Some(existing) => existing.merge(method_router),
+                None => method_router,
+            };
+            paths.insert(path, merged);
+        }
+        paths
+            .into_iter()
+            .fold(Router::new(), |router, (path, method_router)| {
+                router.route(path, method_router)
+            })
+    }
+}
+
+// `Method` 没有实现 `Ord`，以方法名作为键
+fn method_key(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::PATCH => "PATCH",
+        Method::DELETE => "DELETE",
+        _ => panic!("unsupported method {method}"),
+    }
+}
+
+fn require(permission: Permission) -> impl Fn(MethodRouter<AppState>) -> MethodRouter<AppState> {
+    move |method_router| {
+        method_router.route_layer(from_fn_with_state(permission, require_permission))
+    }
+}
+
+/// 各版本共用的接口
+fn common() -> ApiRoutes {
+    let write_post = require(Permission::WritePost);
+    let write_comment = require(Permission::WriteComment);
+    let manage_users = require(Permission::ManageUsers);
+
+    ApiRoutes::default()
+        // 认证相关路由
+        .route("/auth/login", Method::POST, post(auth::login))
+        .route("/auth/refresh", Method::POST, post(auth::refresh))
+        .route("/auth/logout", Method::POST, post(auth::logout))
+        .route("/auth/session", Method::POST, post(auth::session_login))
+        .route(
+            "/auth/session",
+            Method::DELETE,
+            delete(auth::session_logout),
+        )
+        // 文章相关路由
+        .route("/posts", Method::GET, get(posts::list))
+        .route("/posts", Method::POST, write_post(post(posts::create)))
+        .route("/posts/{id}", Method::GET, get(posts::show))
+        .route("/posts/{id}", Method::POST, write_post(post(posts::update)))
+        .route(
+            "/posts/{id}",
+            Method::DELETE,
+            write_post(delete(posts::delete)),
+        )
+        // 标签相关路由
+        .route("/tags", Method::GET, get(tags::list))
+        .route("/tags/{slug}/posts", Method::GET, get(tags::posts))
+        .route(
+            "/posts/{id}/tags",
+            Method::POST,
+            write_post(post(tags::attach)),
+        )
+        .route(
+            "/posts/{id}/tags/{slug}",
+            Method::DELETE,
+            write_post(delete(tags::detach)),
+        )
+        // 用户相关路由
+        .route("/users", Method::GET, manage_users(get(users::list)))
+        .route("/users", Method::POST, post(users::create))
+        // 本人也可以修改，在处理函数中检查
+        .route("/users/{id}", Method::PUT, put(users::update))
+        .route(
+            "/users/{id}",
+            Method::DELETE,
+            manage_users(delete(users::delete)),
+        )
+        .route(
+            "/users/{id}/role",
+            Method::PUT,
+            manage_users(put(users::update_role)),
+        )
+        // 用户的文章路由
+        .route(
+            "/users/{user_id}/posts",
+            Method::GET,
+            get(posts::list_by_user),
+        )
+        // 评论相关路由
+        .route(
+            "/posts/{post_id}/comments",
+            Method::GET,
+            get(comments::list),
+        )
+        .route(
+            "/posts/{post_id}/comments",
+            Method::POST,
+            write_comment(post(comments::create)),
+        )
+        .route(
+            "/posts/{post_id}/comments/{comment_id}",
+            Method::POST,
+            write_comment(post(comments::update)),
+        )
+        .route(
+            "/posts/{post_id}/comments/{comment_id}",
+            Method::DELETE,
+            write_comment(delete(comments::delete)),
+        )
+        .route(
+            "/posts/{post_id}/comments/{comment_id}/replies",
+            Method::GET,
+            get(comments::replies),
+        )
+        // 搜索路由
+        .route("/search/posts", Method::GET, get(posts::search))
+        // 统计路由
+        .route("/statistics", Method::GET, get(posts::statistics))
+        // 测试 span 路由
+        .route("/span/{id}", Method::GET, get(posts::show_span))
+}
+
+/// v1：文章详情中的全部评论已由分页的评论接口取代
+pub fn v1() -> ApiRoutes {
+    let deprecated_at = Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap();
+    let sunset = Utc.with_ymd_and_hms(2027, 4, 18, 0, 0, 0).unwrap();
+    let post_detail = Deprecation::new(deprecated_at.into())
+        .sunset(sunset.into())
+        .successor(V2);
+
+    common().deprecate("/posts/{id}", Method::GET, post_detail)
+}
+
+/// v2：文章详情不再内嵌评论
+pub fn v2() -> ApiRoutes {
+    common().route("/posts/{id}", Method::GET, get(v2::show_post))
+}
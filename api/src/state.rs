@@ -0,0 +1,17 @@
+use crate::health::Health;
+use axum::extract::FromRef;
+use middleware::jwt::JwtKeys;
+use middleware::metrics::Metrics;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// 应用状态
+///
+/// 处理函数可以通过 `State<DatabaseConnection>` 等方式只提取需要的部分。
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub conn: DatabaseConnection,
+    pub jwt: Arc<JwtKeys>,
+    pub metrics: Metrics,
+    pub health: Health,
+}
@@ -0,0 +1,148 @@
+//! 标签相关路由处理模块
+//!
+//! - 获取全部标签
+//! - 获取带有指定标签的文章
+//! - 为文章添加、移除标签
+
+use super::error::{AppError, AppResult, PageResult};
+use super::openapi::schema;
+use super::permission::actor;
+use super::posts::{PostView, post_page};
+use super::request::{ListParams, Listing, PageParams, ViewParams};
+use super::response::{ApiResponse, PageRes};
+use super::validation::{Valid, validate_tags};
+use axum::{
+    Json,
+    extract::{OriginalUri, Path, Query, State},
+};
+use entity::{post, tag};
+use middleware::axum::{RequestContext, UserInfo};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use service::{Mutation as MutationCore, Query as QueryCore};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct TagsInput {
+    /// 标签名，不存在的标签会自动创建
+    #[validate(length(min = 1, max = 20), custom(function = "validate_tags"))]
+    pub tags: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tags",
+    responses((status = 200, body = ApiResponse<Vec<schema::Tag>>))
+)]
+pub async fn list(State(conn): State<DatabaseConnection>) -> AppResult<Vec<tag::Model>> {
+    let tags = QueryCore::find_all_tags(&conn).await?;
+    Ok(Json(ApiResponse::success_with_data(tags)))
+}
+
+// 获取带有指定标签的文章
+#[utoipa::path(
+    get,
+    path = "/tags/{slug}/posts",
+    tag = "tags",
+    params(
+        ("slug" = String, Path, description = "标签 slug"),
+        PageParams,
+        ListParams,
+        ViewParams,
+    ),
+    responses(
+        (status = 200, body = ApiResponse<PageRes<Vec<schema::PostView>>>),
+        (status = 400, description = "Invalid filter or sort", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn posts(
+    State(conn): State<DatabaseConnection>,
+    OriginalUri(uri): OriginalUri,
+    Path(slug): Path<String>,
+    Valid(Query(page_params)): Valid<Query<PageParams>>,
+    listing: Listing<post::Entity>,
+    Query(view): Query<ViewParams>,
+) -> PageResult<PostView> {
+    let page = page_params.page();
+    let size = page_params.size(10);
+    let cursor = listing.check_cursor(page_params.cursor()?)?;
+
+    let tag = QueryCore::find_tag_by_slug(&conn, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tag not found".to_string()))?;
+
+    let posts = match cursor {
+        Some(cursor) => PageRes::from_cursor(
+            QueryCore::find_posts_by_tag_with_cursor(&conn, &tag, &listing.0, Some(cursor), size)
+                .await?,
+            size,
+        ),
+        None => {
+            let (posts, totals) =
+                QueryCore::find_posts_by_tag_in_page(&conn, &tag, &listing.0, page, size).await?;
+            listing.from_page(posts, page, size, totals, |p| p.id)
+        }
+    };
+    Ok(post_page(&conn, posts, &view).await?.with_links(&uri))
+}
+
+// 为文章添加标签，标签不存在时自动创建
+#[utoipa::path(
+    post,
+    path = "/posts/{id}/tags",
+    tag = "tags",
+    params(("id" = i32, Path, description = "文章 ID")),
+    request_body = TagsInput,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<Vec<schema::Tag>>),
+        (status = 400, description = "Invalid request", body = schema::ErrorResponse),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn attach(
+    State(conn): State<DatabaseConnection>,
+    user: UserInfo,
+    ctx: RequestContext,
+    Path(post_id): Path<i32>,
+    Valid(Json(input)): Valid<Json<TagsInput>>,
+) -> AppResult<Vec<tag::Model>> {
+    let tags = MutationCore::attach_tags(&conn, &actor(&user, &ctx), post_id, &input.tags).await?;
+    Ok(Json(ApiResponse::success_with_data(tags)))
+}
+
+// 移除文章上的标签
+#[utoipa::path(
+    delete,
+    path = "/posts/{id}/tags/{slug}",
+    tag = "tags",
+    params(
+        ("id" = i32, Path, description = "文章 ID"),
+        ("slug" = String, Path, description = "标签 slug"),
+    ),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = schema::MessageResponse),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+    )
+)]
+pub async fn detach(
+    State(conn): State<DatabaseConnection>,
+    user: UserInfo,
+    ctx: RequestContext,
+    Path((post_id, slug)): Path<(i32, String)>,
+) -> AppResult<()> {
+    MutationCore::detach_tag(&conn, &actor(&user, &ctx), post_id, &slug).await?;
+    Ok(Json(ApiResponse::<()>::success_with_message(
+        "Tag removed successfully".to_string(),
+    )))
+}
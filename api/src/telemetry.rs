@@ -0,0 +1,117 @@
+//! OpenTelemetry 追踪
+//!
+//! 追踪数据经批处理后通过 OTLP（gRPC 或 HTTP）导出，请求头中的 W3C `traceparent` /
+//! `tracestate` 会作为请求 span 的上游，使本服务的 span 与调用方处于同一条链路。
+
+use crate::config::{TelemetryConfig, TraceExporter};
+use axum::extract::Request;
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use middleware::context::RequestContext;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::{Context, KeyValue, global};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// 创建追踪提供者，并注册为全局提供者和 W3C Trace Context 传播器
+///
+/// 退出前需要调用 `shutdown` 导出缓冲中的 span。
+pub fn tracer_provider(config: &TelemetryConfig) -> anyhow::Result<SdkTracerProvider> {
+    let builder = SdkTracerProvider::builder()
+        .with_resource(resource(config))
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))));
+
+    let builder = match config.exporter {
+        TraceExporter::OtlpGrpc => {
+            let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_tonic();
+            if let Some(endpoint) = &config.endpoint {
+                exporter = exporter.with_endpoint(endpoint);
+            }
+            builder.with_batch_exporter(exporter.build()?)
+        }
+        TraceExporter::OtlpHttp => {
+            let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_http();
+            // 程序中指定的地址会原样使用，需要自己加上信号路径
+            if let Some(endpoint) = &config.endpoint {
+                exporter =
+                    exporter.with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')));
+            }
+            builder.with_batch_exporter(exporter.build()?)
+        }
+        TraceExporter::Stdout => {
+            builder.with_batch_exporter(opentelemetry_stdout::SpanExporter::default())
+        }
+    };
+
+    let provider = builder.build();
+    global::set_tracer_provider(provider.clone());
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(provider)
+}
+
+/// 服务名、版本和部署环境
+fn resource(config: &TelemetryConfig) -> Resource {
+    Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .with_attributes([
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+            KeyValue::new("deployment.environment.name", config.environment.clone()),
+        ])
+        .build()
+}
+
+/// 从请求头中提取上游的追踪上下文，没有或不合法时返回空的上下文
+pub fn extract_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+/// `TraceLayer` 的请求 span，字段与 `DefaultMakeSpan` 一致，另外记录请求 ID，
+/// 并以请求头中的追踪上下文作为上游
+pub fn make_span<B>(request: &axum::http::Request<B>) -> Span {
+    let request_id = request
+        .extensions()
+        .get::<RequestContext>()
+        .map(|data| data.request_id.as_str())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "request",
+        request_id,
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+    );
+    // 未启用 OpenTelemetry 时没有可关联的追踪，忽略即可
+    let _ = span.set_parent(extract_context(request.headers()));
+    span
+}
+
+/// 把当前请求 span 的追踪 ID 写入请求上下文，需要放在 `TraceLayer` 内层
+pub async fn record_trace_id(mut request: Request, next: Next) -> Response {
+    let span_context = Span::current().context().span().span_context().clone();
+    if span_context.is_valid()
+        && let Some(context) = request.extensions_mut().get_mut::<RequestContext>()
+    {
+        context.trace_id = Some(span_context.trace_id().to_string());
+    }
+    next.run(request).await
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
@@ -6,103 +6,289 @@
 //! - 创建新用户
 //! - 更新用户信息
 //! - 删除用户
+//! - 修改用户角色
 
-use crate::response::ApiResponse;
+use crate::error::{AppError, AppResult, PageResult};
+use crate::openapi::schema;
+use crate::permission::actor;
+use crate::request::{ListParams, Listing, PageParams, ViewParams};
+use crate::response::{ApiResponse, PageRes};
+use crate::validation::Valid;
 use axum::{
     Json,
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{OriginalUri, Path, Query as QueryParams, State},
 };
 use bcrypt::{DEFAULT_COST, hash};
-use entity::user;
-use sea_orm::{DatabaseConnection, IntoActiveModel, TryIntoModel};
-use service::{Delete, Query, Save};
+use chrono::{DateTime, Utc};
+use entity::user::{self, Permission};
+use middleware::axum::{RequestContext, UserInfo};
+use sea_orm::{DatabaseConnection, TryIntoModel};
+use serde::{Deserialize, Serialize};
+use service::{Delete, Embedded, Mutation, Query, UserInclude, UserRelations};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// 创建用户的请求参数
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateUser {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    #[validate(email, length(max = 255))]
+    pub email: String,
+    #[validate(length(min = 8, max = 72))]
+    pub password: String,
+}
+
+/// 修改用户的请求参数，未提供的字段保持不变
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct UpdateUser {
+    #[validate(length(min = 1, max = 50))]
+    pub name: Option<String>,
+    #[validate(email, length(max = 255))]
+    pub email: Option<String>,
+    #[validate(length(min = 8, max = 72))]
+    pub password: Option<String>,
+}
+
+/// 对外返回的用户信息，不包含密码摘要
+#[derive(Serialize, ToSchema)]
+pub struct UserView {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub role: user::Role,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<user::Model> for UserView {
+    fn from(user: user::Model) -> Self {
+        UserView {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            role: user.role,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+/// 获取用户列表，支持按字段过滤和排序
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    params(PageParams, ListParams, ViewParams),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<PageRes<Vec<schema::UserWithRelations>>>),
+        (status = 400, description = "Invalid filter or sort", body = schema::ErrorResponse),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
+pub async fn list(
+    State(db): State<DatabaseConnection>,
+    OriginalUri(uri): OriginalUri,
+    Valid(QueryParams(page_params)): Valid<QueryParams<PageParams>>,
+    listing: Listing<user::Entity>,
+    QueryParams(view): QueryParams<ViewParams>,
+) -> PageResult<UserWithRelations> {
+    let page = page_params.page();
+    let size = page_params.size(20);
+
+    let mut users = match listing.check_cursor(page_params.cursor()?)? {
+        Some(cursor) => PageRes::from_cursor(
+            Query::find_users_with_cursor(&db, &listing.0, Some(cursor), size).await?,
+            size,
+        ),
+        None => {
+            let (users, totals) = Query::find_users_in_page(&db, &listing.0, page, size).await?;
+            listing.from_page(users, page, size, totals, |u| u.id)
+        }
+    };
+    let data = std::mem::take(&mut users.data);
+    let users = users.with_data(user_views(&db, data, &view).await?);
+    Ok(users.with_links(&uri))
+}
+
+/// 用户接口可以通过 `fields` 选择的字段
+const USER_FIELDS: &[&str] = &["id", "name", "email", "role", "created_at", "updated_at"];
+
+/// 按 `fields` 和 `include` 生成的用户
+pub type UserWithRelations = Embedded<serde_json::Value, UserRelations>;
+
+/// 为一组用户嵌入关联并去掉未选择的字段
+async fn user_views(
+    db: &DatabaseConnection,
+    users: Vec<user::Model>,
+    view: &ViewParams,
+) -> Result<Vec<UserWithRelations>, AppError> {
+    let fields = view.fields(USER_FIELDS)?;
+    let include = view.include(UserInclude::parse)?;
+    Query::embed_users(db, users, include)
+        .await?
+        .into_iter()
+        .map(|user| {
+            Ok(Embedded {
+                model: fields.select(UserView::from(user.model))?,
+                relations: user.relations,
+            })
+        })
+        .collect()
+}
+
+fn hash_password(password: &str) -> Result<String, AppError> {
+    hash(password, DEFAULT_COST)
+        .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))
+}
 
 /// 创建新用户
+#[utoipa::path(
+    post,
+    path = "/users",
+    tag = "users",
+    request_body = CreateUser,
+    responses(
+        (status = 200, body = ApiResponse<UserView>),
+        (status = 409, description = "Conflict", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn create(
     State(db): State<DatabaseConnection>,
-    Json(mut user): Json<user::Model>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ApiResponse<String>>)> {
+    Valid(Json(input)): Valid<Json<CreateUser>>,
+) -> AppResult<UserView> {
     // 检查用户是否已存在
-    match Query::find_user_by_email(&db, &user.email).await {
-        Ok(Some(_)) => {
-            let error_response = ApiResponse::<String>::error_with_message(
-                "User with this email already exists".to_string(),
-            );
-            Err((StatusCode::CONFLICT, Json(error_response)))
-        }
-        Ok(None) => {
-            // 创建新用户
-            user.password = hash(&user.password, DEFAULT_COST).map_err(|e| {
-                let error_response = ApiResponse::<String>::error_with_message(format!(
-                    "Failed to hash password: {}",
-                    e
-                ));
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
-            })?;
-
-            match Save::save_user(&db, user.into_active_model()).await {
-                Ok(active_user) => match active_user.try_into_model() {
-                    Ok(user_model) => Ok(Json(ApiResponse::success_with_data(user_model))),
-                    Err(_) => {
-                        let error_response = ApiResponse::<String>::error_with_message(
-                            "Failed to convert user model".to_string(),
-                        );
-                        Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                    }
-                },
-                Err(e) => {
-                    let error_response = ApiResponse::<String>::error_with_message(format!(
-                        "Failed to create user: {}",
-                        e
-                    ));
-                    Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-                }
-            }
-        }
-        Err(e) => {
-            let error_response = ApiResponse::<String>::error_with_message(format!(
-                "Failed to check user existence: {}",
-                e
-            ));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-        }
+    if Query::find_user_by_email(&db, &input.email)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict(
+            "User with this email already exists".to_string(),
+        ));
     }
+
+    // 创建新用户，角色只能由管理员修改，ID 和时间戳由服务端生成
+    let now = Utc::now();
+    let user = user::Model {
+        id: 0,
+        name: input.name,
+        email: input.email,
+        password: hash_password(&input.password)?,
+        role: user::Role::User,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let user_model = Mutation::create_user(&db, user).await?.try_into_model()?;
+    Ok(Json(ApiResponse::success_with_data(user_model.into())))
 }
-pub async fn delete(
-    State(db): State<DatabaseConnection>,
-    Path(id): Path<i32>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ApiResponse<String>>)> {
-    match Delete::delete_user(&db, id).await {
-        Ok(_) => Ok(Json(ApiResponse::<String>::success_with_message(
-            "User deleted successfully".to_string(),
-        ))),
-        Err(e) => {
-            let error_response =
-                ApiResponse::<String>::error_with_message(format!("Failed to delete user: {}", e));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-        }
+#[utoipa::path(
+    delete,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "用户 ID")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = schema::MessageResponse),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+    )
+)]
+pub async fn delete(State(db): State<DatabaseConnection>, Path(id): Path<i32>) -> AppResult<()> {
+    let result = Delete::delete_user(&db, id).await?;
+    if result.rows_affected == 0 {
+        return Err(AppError::NotFound("User not found".to_string()));
     }
+
+    Ok(Json(ApiResponse::<()>::success_with_message(
+        "User deleted successfully".to_string(),
+    )))
 }
+
+// 修改用户信息，只有本人或管理员可以修改
+#[utoipa::path(
+    put,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "用户 ID")),
+    request_body = UpdateUser,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<UserView>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+        (status = 409, description = "Conflict", body = schema::ErrorResponse),
+        (status = 422, description = "Validation failed", body = schema::ErrorResponse),
+    )
+)]
 pub async fn update(
     State(db): State<DatabaseConnection>,
-    Json(user): Json<user::Model>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ApiResponse<String>>)> {
-    match Save::save_user(&db, user.into_active_model()).await {
-        Ok(active_user) => match active_user.try_into_model() {
-            Ok(user_model) => Ok(Json(ApiResponse::success_with_data(user_model))),
-            Err(_) => {
-                let error_response = ApiResponse::<String>::error_with_message(
-                    "Failed to convert user model".to_string(),
-                );
-                Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
-            }
-        },
-        Err(e) => {
-            let error_response =
-                ApiResponse::<String>::error_with_message(format!("Failed to create user: {}", e));
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)))
+    current: UserInfo,
+    ctx: RequestContext,
+    Path(id): Path<i32>,
+    Valid(Json(input)): Valid<Json<UpdateUser>>,
+) -> AppResult<UserView> {
+    if !actor(&current, &ctx).can_modify(id, Permission::ManageUsers) {
+        return Err(AppError::Forbidden(
+            "You can only modify your own profile.".to_string(),
+        ));
+    }
+
+    let mut user = Query::find_user_by_id(&db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    if let Some(email) = input.email {
+        if email != user.email && Query::find_user_by_email(&db, &email).await?.is_some() {
+            return Err(AppError::Conflict(
+                "User with this email already exists".to_string(),
+            ));
         }
+        user.email = email;
     }
+    if let Some(name) = input.name {
+        user.name = name;
+    }
+    if let Some(password) = input.password {
+        user.password = hash_password(&password)?;
+    }
+    user.updated_at = Utc::now();
+
+    let user_model = Mutation::update_user_by_id(&db, id, user).await?;
+    Ok(Json(ApiResponse::success_with_data(user_model.into())))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RoleParams {
+    pub role: user::Role,
+}
+
+/// 修改用户角色
+#[utoipa::path(
+    put,
+    path = "/users/{id}/role",
+    tag = "users",
+    params(("id" = i32, Path, description = "用户 ID")),
+    request_body = RoleParams,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<UserView>),
+        (status = 401, description = "Authentication required", body = schema::ErrorResponse),
+        (status = 403, description = "Permission denied", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+    )
+)]
+pub async fn update_role(
+    State(db): State<DatabaseConnection>,
+    Path(id): Path<i32>,
+    Json(params): Json<RoleParams>,
+) -> AppResult<UserView> {
+    let user_model = Mutation::update_user_role(&db, id, params.role).await?;
+    Ok(Json(ApiResponse::success_with_data(user_model.into())))
 }
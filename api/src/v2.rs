@@ -0,0 +1,42 @@
+//! v2 中响应结构有变化的接口
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use sea_orm::DatabaseConnection;
+use service::Query as QueryCore;
+
+use super::error::{AppError, AppResult};
+use super::openapi::schema;
+use super::posts::{PostView, post_views};
+use super::request::ViewParams;
+use super::response::ApiResponse;
+
+// 获取文章，评论通过 /posts/{post_id}/comments 分页获取，或用 `include=comments` 嵌入
+#[utoipa::path(
+    get,
+    path = "/posts/{id}",
+    tag = "posts",
+    params(("id" = i32, Path, description = "文章 ID"), ViewParams),
+    responses(
+        (status = 200, body = ApiResponse<schema::PostView>),
+        (status = 400, description = "Invalid request", body = schema::ErrorResponse),
+        (status = 404, description = "Not found", body = schema::ErrorResponse),
+    )
+)]
+pub async fn show_post(
+    State(conn): State<DatabaseConnection>,
+    Path(id): Path<i32>,
+    Query(view): Query<ViewParams>,
+) -> AppResult<PostView> {
+    let post = QueryCore::find_post_model_by_id(&conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    let post = post_views(&conn, vec![post], &view)
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+    Ok(Json(ApiResponse::success_with_data(post)))
+}
@@ -0,0 +1,138 @@
+//! 请求参数校验
+//!
+//! `Valid` 包装 `Json`、`Form` 和 `Query` 提取器，解析成功后运行 DTO 上
+//! `#[derive(Validate)]` 声明的规则，失败时返回带字段错误的 422 响应：
+//!
+//! ```ignore
+//! pub async fn create(Valid(Json(input)): Valid<Json<PostInput>>) -> AppResult<post::Model>
+//! ```
+
+use crate::error::AppError;
+use axum::{
+    Form, Json,
+    extract::{FromRequest, FromRequestParts, Query, Request},
+    http::request::Parts,
+    response::IntoResponse,
+};
+use serde::Serialize;
+use std::borrow::Cow;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+/// 解析并校验请求参数
+pub struct Valid<T>(pub T);
+
+/// 单个字段的校验错误
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields: Vec<FieldError> = errors
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |e| FieldError {
+                    field: field.to_string(),
+                    code: e.code.to_string(),
+                    message: describe(&field, e),
+                })
+            })
+            .collect();
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        AppError::InvalidFields(fields)
+    }
+}
+
+/// 生成错误描述，规则上声明了 `message` 时优先使用
+fn describe(field: &str, e: &ValidationError) -> String {
+    if let Some(message) = &e.message {
+        return message.to_string();
+    }
+    let param = |name: &str| e.params.get(name).map(|v| v.to_string());
+    match (e.code.as_ref(), param("min"), param("max")) {
+        ("length", Some(min), Some(max)) => {
+            format!("{field} must be between {min} and {max} characters long")
+        }
+        ("length", Some(min), None) => format!("{field} must be at least {min} characters long"),
+        ("length", None, Some(max)) => format!("{field} must be at most {max} characters long"),
+        ("range", Some(min), Some(max)) => format!("{field} must be between {min} and {max}"),
+        ("range", Some(min), None) => format!("{field} must be at least {min}"),
+        ("range", None, Some(max)) => format!("{field} must be at most {max}"),
+        ("email", ..) => format!("{field} must be a valid email address"),
+        (code, ..) => format!("{field} is invalid ({code})"),
+    }
+}
+
+/// 将提取器自身的拒绝（格式错误、缺少字段等）转换为 400
+fn rejection(rejection: impl IntoResponse + ToString) -> AppError {
+    AppError::Validation(rejection.to_string())
+}
+
+impl<S, T> FromRequest<S> for Valid<Json<T>>
+where
+    S: Send + Sync,
+    T: Validate,
+    Json<T>: FromRequest<S, Rejection: IntoResponse + ToString>,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let json = Json::<T>::from_request(req, state)
+            .await
+            .map_err(rejection)?;
+        json.0.validate()?;
+        Ok(Valid(json))
+    }
+}
+
+impl<S, T> FromRequest<S> for Valid<Form<T>>
+where
+    S: Send + Sync,
+    T: Validate,
+    Form<T>: FromRequest<S, Rejection: IntoResponse + ToString>,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let form = Form::<T>::from_request(req, state)
+            .await
+            .map_err(rejection)?;
+        form.0.validate()?;
+        Ok(Valid(form))
+    }
+}
+
+impl<S, T> FromRequestParts<S> for Valid<Query<T>>
+where
+    S: Send + Sync,
+    T: Validate,
+    Query<T>: FromRequestParts<S, Rejection: IntoResponse + ToString>,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let query = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(rejection)?;
+        query.0.validate()?;
+        Ok(Valid(query))
+    }
+}
+
+/// 校验标签列表中的每一项
+pub fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    if tags
+        .iter()
+        .all(|tag| !tag.trim().is_empty() && tag.chars().count() <= 32)
+    {
+        Ok(())
+    } else {
+        Err(ValidationError::new("tags")
+            .with_message(Cow::from("each tag must be 1 to 32 characters long")))
+    }
+}
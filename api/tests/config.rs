@@ -0,0 +1,113 @@
+use api::config::{Config, DEFAULTS, TraceExporter, args_vars, toml_vars};
+use std::collections::HashMap;
+
+fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    DEFAULTS
+        .iter()
+        .chain(pairs)
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn toml_tables_become_prefixed_keys() {
+    let vars = toml_vars(
+        r#"
+        port = 8080
+        [jwt]
+        secret = "s"
+        expires-in = 60
+        [log]
+        file_name = "web.log"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(vars["PORT"], "8080");
+    assert_eq!(vars["JWT_SECRET"], "s");
+    assert_eq!(vars["JWT_EXPIRES_IN"], "60");
+    assert_eq!(vars["LOG_FILE_NAME"], "web.log");
+}
+
+#[test]
+fn cli_flags_become_keys() {
+    let args = ["--port", "8080", "--database-url=mysql://db"].map(String::from);
+    let vars = args_vars(args).unwrap();
+
+    assert_eq!(vars["PORT"], "8080");
+    assert_eq!(vars["DATABASE_URL"], "mysql://db");
+    assert!(args_vars(["--port".to_string()]).is_err());
+}
+
+#[test]
+fn all_problems_are_reported() {
+    let err = Config::from_vars(&vars(&[
+        ("PORT", "http"),
+        ("ENABLE_FILE_LOG", "maybe"),
+        ("TRUSTED_PROXIES", "10.0.0.0/99"),
+    ]))
+    .err()
+    .unwrap()
+    .to_string();
+
+    assert!(err.contains("PORT has an invalid value `http`"));
+    assert!(err.contains("DATABASE_URL is not set"));
+    assert!(err.contains("ENABLE_FILE_LOG must be true or false"));
+    assert!(err.contains("JWT_SECRET is not set"));
+    assert!(err.contains("TRUSTED_PROXIES has an invalid value"));
+}
+
+#[test]
+fn log_levels_fall_back_to_log_level() {
+    let config = Config::from_vars(&vars(&[
+        ("DATABASE_URL", "mysql://db"),
+        ("JWT_SECRET", "0123456789abcdef0123456789abcdef"),
+        ("LOG_LEVEL", "warn"),
+        ("FILE_LOG_LEVEL", "debug"),
+    ]))
+    .unwrap();
+
+    assert_eq!(config.addr(), "127.0.0.1:3000");
+    assert_eq!(config.log.console_level.to_string(), "warn");
+    assert_eq!(config.log.file_level.to_string(), "debug");
+    assert!(config.session.redis_url.is_none());
+    // 启动时不执行迁移，默认不检查
+    assert!(!config.health.check_migrations);
+}
+
+#[test]
+fn telemetry_exporter_and_sampling() {
+    let base = [
+        ("DATABASE_URL", "mysql://db"),
+        ("JWT_SECRET", "0123456789abcdef0123456789abcdef"),
+    ];
+    let config = Config::from_vars(&vars(&base)).unwrap();
+    assert_eq!(config.telemetry.exporter, TraceExporter::OtlpGrpc);
+    assert_eq!(config.telemetry.sample_ratio, 1.0);
+
+    let config = Config::from_vars(&vars(
+        &[
+            &base[..],
+            &[("OTEL_EXPORTER_OTLP_PROTOCOL", "http/protobuf")],
+        ]
+        .concat(),
+    ))
+    .unwrap();
+    assert_eq!(config.telemetry.exporter, TraceExporter::OtlpHttp);
+
+    let err = Config::from_vars(&vars(
+        &[
+            &base[..],
+            &[
+                ("OTEL_TRACES_EXPORTER", "zipkin"),
+                ("OTEL_TRACES_SAMPLER_ARG", "2"),
+            ],
+        ]
+        .concat(),
+    ))
+    .err()
+    .unwrap()
+    .to_string();
+    assert!(err.contains("OTEL_TRACES_EXPORTER must be otlp or stdout"));
+    assert!(err.contains("OTEL_TRACES_SAMPLER_ARG must be between 0 and 1"));
+}
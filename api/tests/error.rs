@@ -0,0 +1,102 @@
+use api::error::AppError;
+use axum::{http::StatusCode, response::IntoResponse};
+use sea_orm::{DbErr, RuntimeErr, SqlErr};
+use serde_json::Value;
+use service::ServiceError;
+
+/// 响应状态码和响应体
+async fn respond(error: impl Into<AppError>) -> (StatusCode, Value) {
+    let response = error.into().into_response();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    // 响应体中的 code 与 HTTP 状态码一致
+    assert_eq!(body["code"], status.as_u16());
+    (status, body)
+}
+
+#[tokio::test]
+async fn missing_record_is_not_found() {
+    let (status, body) = respond(DbErr::RecordNotFound("Cannot find post.".to_string())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Cannot find post.");
+
+    let (status, _) = respond(DbErr::RecordNotUpdated).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = respond(ServiceError::Db(DbErr::RecordNotUpdated)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn constraint_violation_is_conflict() {
+    let unique = SqlErr::UniqueConstraintViolation(
+        "Duplicate entry 'alice@example.com' for key 'user.email'".to_string(),
+    );
+    let (status, body) = respond(AppError::from_sql_err(&unique).unwrap()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "Record already exists");
+
+    let foreign = SqlErr::ForeignKeyConstraintViolation("fk_post_user".to_string());
+    let (status, body) = respond(AppError::from_sql_err(&foreign).unwrap()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(!body["message"].as_str().unwrap().contains("fk_post_user"));
+}
+
+#[tokio::test]
+async fn other_errors_do_not_leak_details() {
+    let secret = "Access denied for user 'app'@'10.0.0.5'";
+    let errors: Vec<AppError> = vec![
+        DbErr::Conn(RuntimeErr::Internal(secret.to_string())).into(),
+        DbErr::Query(RuntimeErr::Internal(secret.to_string())).into(),
+        DbErr::Custom(secret.to_string()).into(),
+        ServiceError::Db(DbErr::Custom(secret.to_string())).into(),
+        AppError::Internal(format!("Session error: {secret}")),
+    ];
+
+    for error in errors {
+        assert!(matches!(
+            error,
+            AppError::Database(_) | AppError::Internal(_)
+        ));
+        let (status, body) = respond(error).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["message"], "Internal server error");
+        assert!(!body.to_string().contains("10.0.0.5"));
+    }
+}
+
+#[tokio::test]
+async fn client_errors_keep_message() {
+    let cases = [
+        (
+            AppError::Conflict("Email already registered".to_string()),
+            StatusCode::CONFLICT,
+        ),
+        (
+            AppError::Validation("Invalid cursor".to_string()),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            AppError::Unauthorized("Invalid token".to_string()),
+            StatusCode::UNAUTHORIZED,
+        ),
+        (
+            AppError::Forbidden("You can only modify your own posts.".to_string()),
+            StatusCode::FORBIDDEN,
+        ),
+        (
+            ServiceError::Invalid("Invalid tag name".to_string()).into(),
+            StatusCode::BAD_REQUEST,
+        ),
+    ];
+
+    for (error, expected) in cases {
+        let message = error.to_string();
+        let (status, body) = respond(error).await;
+        assert_eq!(status, expected);
+        assert_eq!(body["message"], message);
+    }
+}
@@ -0,0 +1,69 @@
+use api::health::{Health, healthz, readyz};
+use axum::body::Body;
+use axum::extract::FromRef;
+use axum::http::{Request, StatusCode};
+use axum::{Router, routing::get};
+use sea_orm::DatabaseConnection;
+use serde_json::Value;
+use tower::ServiceExt;
+
+#[derive(Clone, FromRef)]
+struct State {
+    conn: DatabaseConnection,
+    health: Health,
+}
+
+/// 未连接的数据库，ping 总是失败
+fn app(health: Health) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(State {
+            conn: DatabaseConnection::default(),
+            health,
+        })
+}
+
+async fn get_json(app: Router, uri: &str) -> (StatusCode, Value) {
+    let res = app
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn failed_check_is_not_ready() {
+    let health = Health::new(None, false);
+
+    let (status, body) = get_json(app(health.clone()), "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["shutting_down"], false);
+    assert_eq!(body["checks"]["database"]["status"], "fail");
+    assert!(body["checks"]["database"]["error"].is_string());
+    assert!(body["checks"]["database"]["latency_ms"].is_number());
+    // 未配置 Redis、未开启迁移检查
+    assert_eq!(body["checks"].as_object().unwrap().len(), 1);
+
+    // 存活检查不受依赖影响
+    let (status, body) = get_json(app(health), "/healthz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+}
+
+#[tokio::test]
+async fn shutting_down_is_not_ready() {
+    let health = Health::new(None, false);
+    let app = app(health.clone());
+    health.start_shutdown();
+
+    let (status, body) = get_json(app, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["shutting_down"], true);
+    assert!(body["checks"].as_object().unwrap().is_empty());
+}
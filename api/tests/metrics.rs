@@ -0,0 +1,45 @@
+use api::metrics::{metrics, require_allowed};
+use axum::body::Body;
+use axum::extract::{ConnectInfo, FromRef};
+use axum::http::{Request, StatusCode};
+use axum::{Router, middleware::from_fn_with_state, routing::get};
+use middleware::metrics::Metrics;
+use middleware::tower::LoggingLayer;
+use sea_orm::DatabaseConnection;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+#[derive(Clone, FromRef)]
+struct State {
+    conn: DatabaseConnection,
+    metrics: Metrics,
+}
+
+/// 未连接的数据库，不是 MySQL 连接池
+fn app() -> Router {
+    let allow = "127.0.0.1, 10.0.0.0/8".parse().unwrap();
+    Router::new()
+        .route(
+            "/metrics",
+            get(metrics).route_layer(from_fn_with_state(allow, require_allowed)),
+        )
+        .layer(LoggingLayer::new())
+        .with_state(State {
+            conn: DatabaseConnection::default(),
+            metrics: Metrics::new().unwrap(),
+        })
+}
+
+async fn scrape(peer: &str) -> StatusCode {
+    let mut request = Request::get("/metrics").body(Body::empty()).unwrap();
+    let peer: SocketAddr = peer.parse().unwrap();
+    request.extensions_mut().insert(ConnectInfo(peer));
+    app().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn metrics_only_for_allowed_addresses() {
+    assert_eq!(scrape("127.0.0.1:9000").await, StatusCode::OK);
+    assert_eq!(scrape("10.1.2.3:9000").await, StatusCode::OK);
+    assert_eq!(scrape("203.0.113.9:9000").await, StatusCode::FORBIDDEN);
+}
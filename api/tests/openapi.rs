@@ -0,0 +1,161 @@
+use api::openapi::api_doc;
+use api::routes::{V1, V2, v1, v2};
+use chrono::Utc;
+use entity::user::Role;
+use entity::{comment, post, profile, tag};
+use middleware::axum::UserInfo;
+use serde::Serialize;
+use serde_json::Value;
+use service::{Author, CommentNode, CommentWithAuthor, Embedded, PostRelations};
+use std::collections::BTreeSet;
+
+/// 只用于调试的接口，不写入文档
+const UNDOCUMENTED: &[(&str, &str)] = &[("/span/{id}", "GET")];
+
+fn doc() -> Value {
+    serde_json::to_value(api_doc()).unwrap()
+}
+
+/// 组件的全部属性名，展开引用和 `allOf`（即 `#[serde(flatten)]`）
+fn properties(doc: &Value, schema: &Value) -> BTreeSet<String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/components/schemas/");
+        return properties(doc, &doc["components"]["schemas"][name]);
+    }
+    let mut names: BTreeSet<String> = schema["properties"]
+        .as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default();
+    for part in schema["allOf"].as_array().into_iter().flatten() {
+        names.extend(properties(doc, part));
+    }
+    names
+}
+
+/// 序列化后的字段与文档中同名组件的属性一致
+fn assert_matches<T: Serialize>(doc: &Value, name: &str, value: &T) {
+    let schema = &doc["components"]["schemas"][name];
+    assert!(!schema.is_null(), "schema {name} is not documented");
+    let fields: BTreeSet<String> = serde_json::to_value(value)
+        .unwrap()
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    assert_eq!(properties(doc, schema), fields, "schema {name}");
+}
+
+fn post() -> post::Model {
+    post::Model {
+        id: 1,
+        user_id: 1,
+        title: "Title".to_owned(),
+        body: "Body".to_owned(),
+    }
+}
+
+fn comment() -> comment::Model {
+    comment::Model {
+        id: 1,
+        user_id: 1,
+        post_id: 1,
+        parent_id: None,
+        content: "Comment".to_owned(),
+        deleted_at: Some(Utc::now()),
+    }
+}
+
+fn tag() -> tag::Model {
+    tag::Model {
+        id: 1,
+        name: "Rust".to_owned(),
+        slug: "rust".to_owned(),
+    }
+}
+
+#[test]
+fn schemas_match_serialized_types() {
+    let doc = doc();
+    // 已删除的评论不输出作者和内容，按未删除的评论对照文档
+    let live = comment::Model {
+        deleted_at: None,
+        ..comment()
+    };
+    let with_author = CommentWithAuthor::from((live, None));
+
+    assert_matches(&doc, "Post", &post());
+    assert_matches(&doc, "Comment", &comment());
+    assert_matches(&doc, "Tag", &tag());
+    assert_matches(
+        &doc,
+        "Profile",
+        &profile::Model {
+            id: 1,
+            picture: "a.png".to_owned(),
+            user_id: 1,
+        },
+    );
+    assert_matches(&doc, "CommentWithAuthor", &with_author);
+    assert_matches(
+        &doc,
+        "CommentNode",
+        &CommentNode {
+            comment: with_author.clone(),
+            reply_count: 0,
+            replies: Vec::new(),
+        },
+    );
+    assert_matches(&doc, "UserInfo", &UserInfo::default());
+    // 嵌入全部关联时的字段
+    assert_matches(
+        &doc,
+        "PostView",
+        &Embedded {
+            model: post(),
+            relations: PostRelations {
+                author: Some(Some(Author {
+                    id: 1,
+                    name: "Alice".to_owned(),
+                })),
+                comments: Some(vec![with_author]),
+                comment_count: Some(1),
+                tags: Some(vec![tag()]),
+            },
+        },
+    );
+
+    let roles = &doc["components"]["schemas"]["Role"]["enum"];
+    for role in [Role::Admin, Role::Moderator, Role::User] {
+        let role = serde_json::to_value(role).unwrap();
+        assert!(roles.as_array().unwrap().contains(&role), "role {role}");
+    }
+}
+
+#[test]
+fn documents_every_route() {
+    let doc = doc();
+    let mut routes = BTreeSet::new();
+    for (prefix, version) in [(V1, v1()), (V2, v2())] {
+        for (path, method) in version.endpoints() {
+            if !UNDOCUMENTED.contains(&(path, method)) {
+                routes.insert((format!("{prefix}{path}"), method.to_lowercase()));
+            }
+        }
+    }
+
+    let documented: BTreeSet<(String, String)> = doc["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .filter(|key| ["get", "post", "put", "patch", "delete"].contains(&key.as_str()))
+                .map(move |method| (path.clone(), method.clone()))
+        })
+        .collect();
+
+    assert_eq!(routes, documented);
+}
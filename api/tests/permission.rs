@@ -0,0 +1,179 @@
+use api::auth::DbUserLoader;
+use api::permission::{actor, require_permission};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+    middleware::from_fn_with_state,
+    routing::{delete, get},
+};
+use chrono::Utc;
+use entity::user::{self, Permission, Role};
+use middleware::axum::{AuthState, RequestContext, UserInfo, auth};
+use middleware::jwt::JwtKeys;
+use middleware::session::{AppSessionStore, SESSION_USER_KEY};
+use middleware::test_util::MemoryUsers;
+use sea_orm::{DatabaseBackend, MockDatabase};
+use service::Mutation;
+use std::sync::Arc;
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, Session, SessionManagerLayer};
+
+fn user(role: &str) -> UserInfo {
+    UserInfo {
+        user_id: 7,
+        username: "alice".to_string(),
+        role: role.to_string(),
+    }
+}
+
+/// 与 `routes` 中删除用户的接口相同的权限声明
+fn app(state: AuthState) -> Router {
+    Router::new()
+        .route(
+            "/users/{id}",
+            delete(|| async { "deleted" }).route_layer(from_fn_with_state(
+                Permission::ManageUsers,
+                require_permission,
+            )),
+        )
+        // 以 7 号用户的身份登录
+        .route(
+            "/login",
+            get(|session: Session| async move {
+                session.insert(SESSION_USER_KEY, 7u32).await.unwrap();
+            }),
+        )
+        .layer(from_fn_with_state(state, auth))
+        .layer(
+            SessionManagerLayer::new(AppSessionStore::Memory(MemoryStore::default()))
+                .with_secure(false),
+        )
+}
+
+async fn delete_user(role: Option<&str>) -> StatusCode {
+    let state = AuthState::for_tests(MemoryUsers::default());
+    let app = app(state.clone());
+
+    let mut request = Request::delete("/users/1");
+    if let Some(role) = role {
+        let token = state.keys.encode(&user(role)).unwrap();
+        request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    app.oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn require_permission_checks_role() {
+    assert_eq!(delete_user(None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(delete_user(Some("user")).await, StatusCode::FORBIDDEN);
+    assert_eq!(delete_user(Some("moderator")).await, StatusCode::FORBIDDEN);
+    assert_eq!(delete_user(Some("admin")).await, StatusCode::OK);
+    // 无法识别的角色按普通用户处理
+    assert_eq!(delete_user(Some("root")).await, StatusCode::FORBIDDEN);
+}
+
+fn user_model(role: Role) -> user::Model {
+    user::Model {
+        id: 7,
+        name: "alice".to_owned(),
+        email: "alice@example.com".to_owned(),
+        password: String::new(),
+        role,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn demoted_session_loses_permission() {
+    let conn = MockDatabase::new(DatabaseBackend::Postgres)
+        // 降级前的请求读取到的角色
+        .append_query_results([[user_model(Role::Admin)]])
+        // update_user_role 先查询再更新
+        .append_query_results([[user_model(Role::Admin)]])
+        .append_query_results([[user_model(Role::User)]])
+        // 降级后重放会话读取到的角色
+        .append_query_results([[user_model(Role::User)]])
+        .into_connection();
+    let users = Arc::new(DbUserLoader(conn));
+    let app = app(AuthState {
+        keys: Arc::new(JwtKeys::for_tests()),
+        users: users.clone(),
+    });
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/login").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let cookie = response.headers()[header::SET_COOKIE]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    let replay = || {
+        app.clone().oneshot(
+            Request::delete("/users/1")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    assert_eq!(replay().await.unwrap().status(), StatusCode::OK);
+    Mutation::update_user_role(&users.0, 7, Role::User)
+        .await
+        .unwrap();
+    assert_eq!(replay().await.unwrap().status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn role_permissions() {
+    use Permission::*;
+    let all = [
+        WritePost,
+        WriteComment,
+        ManagePosts,
+        ManageComments,
+        ManageUsers,
+    ];
+    let table = [
+        (Role::Admin, &all[..]),
+        (
+            Role::Moderator,
+            &[WritePost, WriteComment, ManagePosts, ManageComments][..],
+        ),
+        (Role::User, &[WritePost, WriteComment][..]),
+    ];
+
+    for (role, granted) in table {
+        for permission in all {
+            assert_eq!(
+                role.has(permission),
+                granted.contains(&permission),
+                "{role:?} {permission:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn owner_or_manager_can_modify() {
+    let ctx = RequestContext::new(
+        &Default::default(),
+        &Default::default(),
+        &Default::default(),
+    );
+
+    let owner = actor(&user("user"), &ctx);
+    assert!(owner.can_modify(7, Permission::ManageUsers));
+    assert!(!owner.can_modify(8, Permission::ManageUsers));
+    assert!(actor(&user("admin"), &ctx).can_modify(8, Permission::ManageUsers));
+    assert_eq!(owner.request_id.as_deref(), Some(ctx.request_id.as_str()));
+}
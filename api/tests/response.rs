@@ -0,0 +1,203 @@
+use api::error::AppError;
+use api::request::{Listing, encode_cursor};
+use api::response::PageRes;
+use axum::{
+    http::{Uri, header},
+    response::IntoResponse,
+};
+use entity::{post, user};
+use sea_orm::ItemsAndPagesNumber;
+use serde_json::Value;
+use service::{Cursor, CursorPage, ListQuery};
+
+/// 与 sea-orm 的分页器一样，总页数向上取整
+fn totals(items: u64, size: u64) -> ItemsAndPagesNumber {
+    ItemsAndPagesNumber {
+        number_of_items: items,
+        number_of_pages: items.div_ceil(size),
+    }
+}
+
+/// 第 `page` 页的记录主键
+fn ids(page: u64, size: u64, items: u64) -> Vec<i32> {
+    let start = (page - 1) * size + 1;
+    (start..=items.min(page * size))
+        .map(|id| id as i32)
+        .collect()
+}
+
+fn page(page: u64, size: u64, items: u64) -> PageRes<Vec<i32>> {
+    PageRes::from_page(
+        ids(page, size, items),
+        page,
+        size,
+        totals(items, size),
+        |id| *id,
+    )
+}
+
+/// `Link` 头和响应体
+async fn links(page: PageRes<Vec<i32>>, uri: &str) -> (String, Value) {
+    let response = page
+        .with_links(&uri.parse::<Uri>().unwrap())
+        .into_response();
+    let link = response.headers()[header::LINK]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (link, serde_json::from_slice(&body).unwrap())
+}
+
+#[test]
+fn from_page() {
+    let first = page(1, 10, 21);
+    assert_eq!(first.total_items, Some(21));
+    assert_eq!(first.total_pages, Some(3));
+    assert!(first.has_next);
+    assert_eq!(first.next_cursor, Some(encode_cursor(10)));
+    assert_eq!(first.prev_cursor, None);
+
+    let middle = page(2, 10, 21);
+    assert!(middle.has_next);
+    assert_eq!(middle.next_cursor, Some(encode_cursor(20)));
+    assert_eq!(middle.prev_cursor, Some(encode_cursor(11)));
+
+    // 最后一页只有一条记录
+    let last = page(3, 10, 21);
+    assert_eq!(last.data, vec![21]);
+    assert!(!last.has_next);
+    assert_eq!(last.next_cursor, None);
+    assert_eq!(last.prev_cursor, Some(encode_cursor(21)));
+
+    // 正好整除时不多出一页
+    let exact = page(2, 10, 20);
+    assert_eq!(exact.total_pages, Some(2));
+    assert!(!exact.has_next);
+}
+
+#[test]
+fn sorted_page_has_no_cursors() {
+    let listing = |query: &[(&str, &str)]| {
+        Listing::<post::Entity>(ListQuery::parse(query.iter().copied()).unwrap())
+    };
+    let sorted = |listing: Listing<post::Entity>| {
+        listing.from_page(ids(2, 10, 21), 2, 10, totals(21, 10), |id| *id)
+    };
+
+    let page = sorted(listing(&[("filter[user_id]", "3")]));
+    assert_eq!(page.next_cursor, Some(encode_cursor(20)));
+    assert_eq!(page.prev_cursor, Some(encode_cursor(11)));
+
+    // 游标分页只按主键排序，不能接着其他排序翻页
+    let page = sorted(listing(&[("sort", "-title")]));
+    assert!(page.has_next);
+    assert_eq!((page.next_cursor, page.prev_cursor), (None, None));
+}
+
+#[test]
+fn cursor_requires_id_order() {
+    let listing = |query: &[(&str, &str)]| {
+        Listing::<user::Entity>(ListQuery::parse(query.iter().copied()).unwrap())
+    };
+    let cursor = Some(Cursor::After(10));
+
+    assert_eq!(listing(&[]).check_cursor(cursor).unwrap(), cursor);
+    assert_eq!(
+        listing(&[("sort", "-created_at")])
+            .check_cursor(None)
+            .unwrap(),
+        None
+    );
+    // 游标只记录主键，不能按创建时间等其他字段接着翻页
+    assert!(matches!(
+        listing(&[("sort", "-created_at")]).check_cursor(cursor),
+        Err(AppError::Validation(_))
+    ));
+}
+
+#[test]
+fn from_page_empty() {
+    let empty = page(1, 10, 0);
+    assert!(empty.data.is_empty());
+    assert_eq!(empty.total_items, Some(0));
+    assert_eq!(empty.total_pages, Some(0));
+    assert!(!empty.has_next);
+    assert_eq!((empty.next_cursor, empty.prev_cursor), (None, None));
+
+    // 超出最后一页
+    let beyond = page(5, 10, 21);
+    assert!(beyond.data.is_empty());
+    assert!(!beyond.has_next);
+    assert_eq!((beyond.next_cursor, beyond.prev_cursor), (None, None));
+}
+
+#[tokio::test]
+async fn page_links() {
+    let (link, body) = links(
+        page(2, 10, 21),
+        "/api/v1/posts?sort=-id&page=2&size=10&after=abc&tag=rust",
+    )
+    .await;
+    assert_eq!(
+        link,
+        "</api/v1/posts?sort=-id&size=10&tag=rust>; rel=\"first\", \
+         </api/v1/posts?sort=-id&size=10&tag=rust&page=1>; rel=\"prev\", \
+         </api/v1/posts?sort=-id&size=10&tag=rust&page=3>; rel=\"next\", \
+         </api/v1/posts?sort=-id&size=10&tag=rust&page=3>; rel=\"last\""
+    );
+    assert_eq!(body["data"]["page"], 2);
+    assert_eq!(body["data"]["total_pages"], 3);
+
+    // 最后一页没有 next
+    let (link, _) = links(page(3, 10, 21), "/api/v1/posts?page=3").await;
+    assert_eq!(
+        link,
+        "</api/v1/posts>; rel=\"first\", \
+         </api/v1/posts?page=2>; rel=\"prev\", \
+         </api/v1/posts?page=3>; rel=\"last\""
+    );
+
+    // 没有数据时最后一页仍为第 1 页，超出范围时 prev 指向最后一页
+    let (link, _) = links(page(1, 10, 0), "/api/v1/posts").await;
+    assert_eq!(
+        link,
+        "</api/v1/posts>; rel=\"first\", </api/v1/posts?page=1>; rel=\"last\""
+    );
+    let (link, _) = links(page(5, 10, 21), "/api/v1/posts?page=5").await;
+    assert_eq!(
+        link,
+        "</api/v1/posts>; rel=\"first\", \
+         </api/v1/posts?page=3>; rel=\"prev\", \
+         </api/v1/posts?page=3>; rel=\"last\""
+    );
+}
+
+#[tokio::test]
+async fn cursor_links() {
+    let cursor_page = CursorPage {
+        items: vec![11, 12],
+        next: Some(12),
+        prev: Some(11),
+    };
+    let (link, body) = links(
+        PageRes::from_cursor(cursor_page, 2),
+        "/api/v1/posts?after=abc&size=2&page=4",
+    )
+    .await;
+    assert_eq!(
+        link,
+        format!(
+            "</api/v1/posts?size=2>; rel=\"first\", \
+             </api/v1/posts?size=2&before={}>; rel=\"prev\", \
+             </api/v1/posts?size=2&after={}>; rel=\"next\"",
+            encode_cursor(11),
+            encode_cursor(12),
+        )
+    );
+    assert!(body["data"]["page"].is_null());
+    assert!(body["data"]["total_pages"].is_null());
+    assert_eq!(body["data"]["has_next"], true);
+}
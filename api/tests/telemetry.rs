@@ -0,0 +1,66 @@
+use api::config::{TelemetryConfig, TraceExporter};
+use api::telemetry::{make_span, tracer_provider};
+use axum::{Router, body::Bytes, http::HeaderMap, http::Request, routing::post};
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use tokio::sync::mpsc;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+fn config(exporter: TraceExporter, endpoint: Option<String>) -> TelemetryConfig {
+    TelemetryConfig {
+        exporter,
+        endpoint,
+        service_name: "rs-web-test".into(),
+        environment: "test".into(),
+        sample_ratio: 1.0,
+    }
+}
+
+/// 代替 OTLP 收集器，把收到的请求头转发出来
+async fn collector() -> (String, mpsc::UnboundedReceiver<HeaderMap>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/v1/traces",
+        post(move |headers: HeaderMap, body: Bytes| async move {
+            assert!(!body.is_empty());
+            tx.send(headers).unwrap();
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{addr}"), rx)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_span_joins_incoming_trace_and_is_exported() {
+    let (endpoint, mut received) = collector().await;
+    let provider = tracer_provider(&config(TraceExporter::OtlpHttp, Some(endpoint))).unwrap();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+    let request = Request::get("/api/v1/posts")
+        .header("traceparent", format!("00-{TRACE_ID}-00f067aa0ba902b7-01"))
+        .body(())
+        .unwrap();
+    let trace_id = tracing::subscriber::with_default(subscriber, || {
+        let span = make_span(&request);
+        let trace_id = span.context().span().span_context().trace_id();
+        drop(span);
+        trace_id
+    });
+    assert_eq!(trace_id.to_string(), TRACE_ID);
+
+    provider.force_flush().unwrap();
+    let headers = received.recv().await.unwrap();
+    assert_eq!(headers["content-type"], "application/x-protobuf");
+    provider.shutdown().unwrap();
+}
+
+#[test]
+fn rejects_invalid_endpoint() {
+    let config = config(TraceExporter::OtlpHttp, Some("not a url".into()));
+    assert!(tracer_provider(&config).is_err());
+}
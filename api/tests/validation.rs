@@ -0,0 +1,183 @@
+use api::request::{MAX_PAGE_SIZE, PageParams};
+use api::validation::{Valid, validate_tags};
+use axum::{
+    Form, Json, Router,
+    body::Body,
+    extract::Query,
+    http::{Request, StatusCode, header},
+    routing::{get, post},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+struct Input {
+    #[validate(length(min = 3, max = 10))]
+    name: String,
+    #[validate(email)]
+    email: String,
+    #[validate(custom(function = "validate_tags"))]
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/json",
+            post(|Valid(Json(input)): Valid<Json<Input>>| async move { input.name }),
+        )
+        .route(
+            "/form",
+            post(|Valid(Form(input)): Valid<Form<Input>>| async move { input.name }),
+        )
+        .route(
+            "/query",
+            get(|Valid(Query(input)): Valid<Query<Input>>| async move { input.name }),
+        )
+        .route(
+            "/page",
+            get(|Valid(Query(page)): Valid<Query<PageParams>>| async move {
+                format!("{} {}", page.page(), page.size(20))
+            }),
+        )
+}
+
+async fn send(request: Request<Body>) -> (StatusCode, Value) {
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body =
+        serde_json::from_slice(&body).unwrap_or_else(|_| json!(String::from_utf8_lossy(&body)));
+    (status, body)
+}
+
+fn json_request(body: Value) -> Request<Body> {
+    Request::post("/json")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn get_request(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+/// 字段错误按字段名排序，`code` 与 HTTP 状态码一致
+fn assert_invalid(status: StatusCode, body: &Value, fields: &[(&str, &str)]) {
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], 422);
+    assert_eq!(body["message"], "Validation failed");
+    let errors: Vec<(&str, &str)> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["field"].as_str().unwrap(), e["code"].as_str().unwrap()))
+        .collect();
+    assert_eq!(errors, fields);
+    assert!(body["data"][0]["message"].is_string());
+}
+
+#[tokio::test]
+async fn json_rejections() {
+    let (status, body) = send(json_request(json!({
+        "name": "al",
+        "email": "alice",
+        "tags": ["rust", " "],
+    })))
+    .await;
+    assert_invalid(
+        status,
+        &body,
+        &[("email", "email"), ("name", "length"), ("tags", "tags")],
+    );
+    assert_eq!(
+        body["data"][1]["message"],
+        "name must be between 3 and 10 characters long"
+    );
+    assert_eq!(
+        body["data"][2]["message"],
+        "each tag must be 1 to 32 characters long"
+    );
+
+    // 格式错误或缺少字段由提取器拒绝，返回 400
+    let (status, body) = send(json_request(json!({ "name": "alice" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], 400);
+
+    let (status, body) = send(json_request(json!({
+        "name": "alice",
+        "email": "alice@example.com",
+    })))
+    .await;
+    assert_eq!((status, body), (StatusCode::OK, json!("alice")));
+}
+
+#[tokio::test]
+async fn form_rejections() {
+    let request = |body: &'static str| {
+        Request::post("/form")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let (status, body) = send(request("name=alice&email=not-an-email")).await;
+    assert_invalid(status, &body, &[("email", "email")]);
+
+    let (status, _) = send(request("name=alice&email=alice%40example.com")).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn query_rejections() {
+    let (status, body) = send(get_request("/query?name=a-very-long-name&email=x")).await;
+    assert_invalid(status, &body, &[("email", "email"), ("name", "length")]);
+
+    let (status, body) = send(get_request("/query?name=alice")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], 400);
+}
+
+#[tokio::test]
+async fn page_params() {
+    let (status, body) = send(get_request("/page")).await;
+    assert_eq!((status, body), (StatusCode::OK, json!("1 20")));
+
+    let (status, body) = send(get_request("/page?page=0")).await;
+    assert_invalid(status, &body, &[("page", "range")]);
+
+    let (status, body) = send(get_request("/page?size=100000")).await;
+    assert_invalid(status, &body, &[("size", "range")]);
+    assert_eq!(body["data"][0]["message"], "size must be between 1 and 100");
+
+    // 未经过校验时也限制在合法范围内
+    let page = PageParams {
+        page: Some(0),
+        size: Some(100_000),
+        after: None,
+        before: None,
+    };
+    assert_eq!(page.page(), 1);
+    assert_eq!(page.size(20), MAX_PAGE_SIZE);
+    let page = PageParams {
+        size: Some(0),
+        ..page
+    };
+    assert_eq!(page.size(20), 1);
+}
+
+#[test]
+fn tags() {
+    assert!(validate_tags(&[]).is_ok());
+    assert!(validate_tags(&["rust".to_string(), "数据库".repeat(10)]).is_ok());
+    assert!(validate_tags(&["".to_string()]).is_err());
+    assert!(validate_tags(&["   ".to_string()]).is_err());
+    // 按字符而不是字节计算长度
+    assert!(validate_tags(&["汉".repeat(32)]).is_ok());
+    assert!(validate_tags(&["a".repeat(33)]).is_err());
+}
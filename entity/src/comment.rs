@@ -1,19 +1,32 @@
+use chrono::{DateTime, Utc};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[sea_orm::model]
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema), schema(as = Comment))]
 #[sea_orm(table_name = "comment")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub user_id: i32,
     pub post_id: i32,
+    /// 回复的评论，顶层评论为空
+    pub parent_id: Option<i32>,
+    /// 已删除的评论内容为空
     pub content: String,
+    /// 删除时间，仍有回复的评论删除后只保留占位
+    pub deleted_at: Option<DateTime<Utc>>,
     #[sea_orm(belongs_to, from = "user_id", to = "id")]
+    #[cfg_attr(feature = "utoipa", schema(ignore))]
     pub author: HasOne<super::user::Entity>,
     #[sea_orm(belongs_to, from = "post_id", to = "id")]
+    #[cfg_attr(feature = "utoipa", schema(ignore))]
     pub post: HasOne<super::post::Entity>,
+    //  自引用
+    #[sea_orm(self_ref, relation_enum = "Parent", from = "parent_id", to = "id")]
+    #[cfg_attr(feature = "utoipa", schema(ignore))]
+    pub parent: HasOne<Entity>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -2,4 +2,6 @@ pub mod comment;
 pub mod post;
 pub mod post_tag;
 pub mod profile;
+pub mod refresh_token;
+pub mod tag;
 pub mod user;
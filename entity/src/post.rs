@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 #[sea_orm::model]
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema), schema(as = Post))]
 #[sea_orm(table_name = "post")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -11,11 +12,14 @@ pub struct Model {
     pub title: String,
     pub body: String,
     #[sea_orm(belongs_to, from = "user_id", to = "id")]
+    #[cfg_attr(feature = "utoipa", schema(ignore))]
     pub author: HasOne<super::user::Entity>,
     #[sea_orm(has_many)]
+    #[cfg_attr(feature = "utoipa", schema(ignore))]
     pub comments: HasMany<super::comment::Entity>,
-    // #[sea_orm(has_many, via = "post_tag")]
-    // pub tags: HasMany<super::tag::Entity>,
+    #[sea_orm(has_many, via = "post_tag")]
+    #[cfg_attr(feature = "utoipa", schema(ignore))]
+    pub tags: HasMany<super::tag::Entity>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
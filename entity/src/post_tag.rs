@@ -10,8 +10,8 @@ pub struct Model {
     pub tag_id: i32,
     #[sea_orm(belongs_to, from = "post_id", to = "id")]
     pub post: Option<super::post::Entity>,
-    // #[sea_orm(belongs_to, from = "tag_id", to = "id")]
-    // pub tag: Option<super::tag::Entity>,
+    #[sea_orm(belongs_to, from = "tag_id", to = "id")]
+    pub tag: Option<super::tag::Entity>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
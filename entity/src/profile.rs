@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 #[sea_orm::model]
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema), schema(as = Profile))]
 #[sea_orm(table_name = "profile")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -11,6 +12,7 @@ pub struct Model {
     #[sea_orm(unique)]
     pub user_id: i32,
     #[sea_orm(belongs_to, from = "user_id", to = "id")]
+    #[cfg_attr(feature = "utoipa", schema(ignore))]
     pub user: HasOne<super::user::Entity>,
 }
 
@@ -10,7 +10,11 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub email: String,
+    /// bcrypt 摘要，不参与序列化
+    #[serde(skip_serializing)]
     pub password: String,
+    #[sea_orm(default_value = "user")]
+    pub role: Role,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     #[sea_orm(has_one)]
@@ -20,3 +24,64 @@ pub struct Model {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+/// 用户角色
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[sea_orm(string_value = "admin")]
+    Admin,
+    #[sea_orm(string_value = "moderator")]
+    Moderator,
+    #[default]
+    #[sea_orm(string_value = "user")]
+    User,
+}
+
+/// 权限
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    /// 发布、修改、删除自己的文章
+    WritePost,
+    /// 发布、修改、删除自己的评论
+    WriteComment,
+    /// 修改、删除任意文章
+    ManagePosts,
+    /// 修改、删除任意评论
+    ManageComments,
+    /// 修改、删除任意用户
+    ManageUsers,
+}
+
+impl Role {
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Admin => &[
+                WritePost,
+                WriteComment,
+                ManagePosts,
+                ManageComments,
+                ManageUsers,
+            ],
+            Role::Moderator => &[WritePost, WriteComment, ManagePosts, ManageComments],
+            Role::User => &[WritePost, WriteComment],
+        }
+    }
+
+    pub fn has(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = DbErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::try_from_value(&s.to_string())
+    }
+}
@@ -1,66 +1,179 @@
-use axum::{http::Request, middleware::Next, response::IntoResponse};
+use crate::context::TrustedProxies;
+use crate::jwt::{JwtError, JwtKeys};
+use crate::session::SESSION_USER_KEY;
+use async_trait::async_trait;
+use axum::{
+    Json,
+    extract::{FromRequestParts, State},
+    http::{HeaderValue, Request, StatusCode, header, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
 use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+use tower_sessions::Session;
 
 // 共享数据结构
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 pub struct UserInfo {
     pub user_id: u32,
     pub username: String,
+    /// 角色：`admin`、`moderator` 或 `user`
     pub role: String,
 }
 
-#[derive(Debug, Clone)]
-pub struct RequestContext {
-    pub user_info: Option<UserInfo>,
+pub use crate::context::RequestContext;
+
+/// 按 ID 读取用户的当前信息，由应用从用户表实现
+///
+/// 会话中只保存用户 ID，角色等信息在每个请求中重新读取，
+/// 修改角色或删除用户后，已登录的会话在下一个请求中就会生效。
+#[async_trait]
+pub trait UserLoader: Send + Sync {
+    /// 用户不存在时返回 `Ok(None)`
+    async fn load_user(
+        &self,
+        user_id: u32,
+    ) -> Result<Option<UserInfo>, Box<dyn Error + Send + Sync>>;
+}
+
+/// 认证中间件的状态
+#[derive(Clone)]
+pub struct AuthState {
+    /// 校验 Bearer 令牌的密钥
+    pub keys: Arc<JwtKeys>,
+    /// 读取会话中登录用户的当前信息
+    pub users: Arc<dyn UserLoader>,
+}
+
+/// 在处理函数中提取当前登录用户，未登录时返回 401
+impl<S> FromRequestParts<S> for UserInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<RequestContext>()
+            .and_then(|ctx| ctx.user_info.clone())
+            .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, "Authentication required"))
+    }
+}
+
+/// 与 api 中 `ApiResponse` 结构一致的错误响应
+pub fn error_response(status: StatusCode, message: &str) -> Response {
+    let mut body = serde_json::json!({
+        "code": status.as_u16(),
+        "message": message,
+        "data": null,
+    });
+    if let Some(request_id) = crate::tower::current_request_id() {
+        body["request_id"] = request_id.into();
+    }
+    (status, Json(body)).into_response()
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response = error_response(StatusCode::UNAUTHORIZED, message);
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static("Bearer error=\"invalid_token\""),
+    );
+    response
 }
 
 // 单个中间件完成认证和添加用户信息到响应
-pub async fn auth(mut req: Request<axum::body::Body>, next: Next) -> impl IntoResponse {
+//
+// 优先使用 Authorization 头中的 Bearer 令牌，未携带时退回到会话中的登录用户，
+// 都没有的请求以匿名身份继续处理。携带了但令牌非法或过期的请求直接返回 401。
+// 会话用户的角色每次从 `UserLoader` 读取，不信任写入会话时的角色。
+pub async fn auth(
+    State(AuthState { keys, users }): State<AuthState>,
+    mut req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
     // 从请求头中获取认证令牌
     let auth_header = req
         .headers()
-        .get("authorization")
-        .and_then(|header| header.to_str().ok());
-
-    tracing::error!("Axum Authorization header: {:?}", auth_header);
+        .get(header::AUTHORIZATION)
+        .map(|header| header.to_str().unwrap_or_default());
 
     // 验证令牌并获取用户信息
-    let user_info = auth_header.map(|_token| UserInfo {
-        user_id: 123,
-        username: "test_user".to_string(),
-        role: "admin".to_string(),
-    });
-
-    // 创建请求上下文
-    let context = RequestContext {
-        user_info: user_info.clone(),
+    let user_info = match auth_header {
+        None => session_user(req.extensions().get::<Session>().cloned(), users.as_ref()).await,
+        Some(value) => {
+            let token = match value.split_once(' ') {
+                Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => token.trim(),
+                _ => return unauthorized("Invalid authorization header"),
+            };
+            match keys.decode(token).and_then(UserInfo::try_from) {
+                Ok(user) => Some(user),
+                Err(e @ (JwtError::Expired | JwtError::InvalidToken(_))) => {
+                    tracing::debug!(error = %e, "Rejected bearer token");
+                    return unauthorized(&e.to_string());
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to verify bearer token");
+                    return unauthorized("Invalid token");
+                }
+            }
+        }
     };
 
-    // 将上下文添加到请求扩展中，供业务逻辑使用
-    req.extensions_mut().insert(context);
-    let user_info = req
-        .extensions()
-        .get::<RequestContext>()
-        .and_then(|ctx| ctx.user_info.clone());
+    // 将用户信息写入请求上下文，供业务逻辑使用，没有经过 `LoggingLayer` 时在这里创建，不信任任何代理
+    match req.extensions_mut().get_mut::<RequestContext>() {
+        Some(context) => context.user_info = user_info.clone(),
+        None => {
+            let mut context =
+                RequestContext::new(req.headers(), req.extensions(), &TrustedProxies::default());
+            context.user_info = user_info.clone();
+            req.extensions_mut().insert(context);
+        }
+    }
     // 执行业务逻辑
     let mut response = next.run(req).await;
 
     // 如果有用户信息，添加到响应头中
     if let Some(user) = user_info {
-        response
-            .headers_mut()
-            .insert("axum-User-ID", user.user_id.to_string().parse().unwrap());
-        response
-            .headers_mut()
-            .insert("axum-Username", user.username.parse().unwrap());
-        response
-            .headers_mut()
-            .insert("axum-User-Role", user.role.parse().unwrap());
+        let headers = response.headers_mut();
+        headers.insert("axum-User-ID", HeaderValue::from(user.user_id));
+        if let Ok(username) = HeaderValue::from_str(&user.username) {
+            headers.insert("axum-Username", username);
+        }
+        if let Ok(role) = HeaderValue::from_str(&user.role) {
+            headers.insert("axum-User-Role", role);
+        }
     }
 
     response
 }
 
-pub fn get_request_context(req: &Request<axum::body::Body>) -> Option<&RequestContext> {
-    req.extensions().get::<RequestContext>()
+async fn session_user(session: Option<Session>, users: &dyn UserLoader) -> Option<UserInfo> {
+    let session = session?;
+    let user_id = match session.get::<u32>(SESSION_USER_KEY).await {
+        Ok(user_id) => user_id?,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load user from session");
+            return None;
+        }
+    };
+    match users.load_user(user_id).await {
+        Ok(Some(user)) => Some(user),
+        // 用户已被删除，会话随之失效
+        Ok(None) => {
+            tracing::debug!(user_id, "Session user no longer exists");
+            if let Err(e) = session.flush().await {
+                tracing::error!(error = %e, "Failed to flush session");
+            }
+            None
+        }
+        Err(e) => {
+            tracing::error!(error = %e, user_id, "Failed to load session user");
+            None
+        }
+    }
 }
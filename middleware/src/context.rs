@@ -0,0 +1,212 @@
+//! 请求上下文
+//!
+//! `LoggingLayer` 在请求进入时创建，写入请求 ID、客户端地址和开始时间，
+//! 客户端地址按配置的可信代理从 `X-Forwarded-For` 中确定，见 `TrustedProxies`；
+//! 认证中间件补充当前用户，应用可以在追踪层内补充追踪 ID。
+//! 处理函数直接以 `RequestContext` 作为参数提取。
+
+use crate::axum::UserInfo;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use http::{Extensions, HeaderMap, request::Parts};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// 请求 ID 的请求头和响应头
+pub static X_REQUEST_ID: http::HeaderName = http::HeaderName::from_static("x-request-id");
+
+/// 请求头中的请求 ID 最长保留的长度
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// 请求 ID，与 `X-Request-ID` 相同
+    pub request_id: String,
+    /// 当前登录用户，匿名请求为空
+    pub user_info: Option<UserInfo>,
+    /// 客户端地址，见 `TrustedProxies::client_ip`
+    pub client_ip: Option<IpAddr>,
+    /// 开始处理请求的时间
+    pub started_at: SystemTime,
+    /// OpenTelemetry 追踪 ID，未启用追踪时为空
+    pub trace_id: Option<String>,
+}
+
+impl RequestContext {
+    /// 从请求头和请求扩展创建，沿用请求头中合法的请求 ID，没有时生成新的
+    pub fn new(headers: &HeaderMap, extensions: &Extensions, proxies: &TrustedProxies) -> Self {
+        let request_id = headers
+            .get(&X_REQUEST_ID)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| is_valid_request_id(id))
+            .map(str::to_string)
+            // UUIDv7 以时间戳开头，按生成顺序排序，并发生成也不会重复
+            .unwrap_or_else(|| Uuid::now_v7().to_string());
+
+        RequestContext {
+            request_id,
+            user_info: None,
+            client_ip: proxies.client_ip(headers, extensions),
+            started_at: SystemTime::now(),
+            trace_id: None,
+        }
+    }
+
+    /// 从开始处理到现在经过的时间
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed().unwrap_or_default()
+    }
+}
+
+/// 在处理函数中提取请求上下文，没有经过 `LoggingLayer` 时现场创建，不信任任何代理
+impl<S> FromRequestParts<S> for RequestContext
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(match parts.extensions.get::<RequestContext>() {
+            Some(context) => context.clone(),
+            None => RequestContext::new(
+                &parts.headers,
+                &parts.extensions,
+                &TrustedProxies::default(),
+            ),
+        })
+    }
+}
+
+/// 只接受长度有限的字母、数字和 `-_.:`，避免伪造的请求头污染日志
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// 可信的反向代理，由地址或网段组成，如 `10.0.0.0/8, 127.0.0.1`
+///
+/// 只有直接连接的对端属于可信代理时才读取转发头：从右往左遍历 `X-Forwarded-For`，
+/// 跳过可信代理，第一个不可信的地址即为客户端地址。最左边的地址由客户端自己填写，
+/// 不能直接采用。默认为空，不信任任何转发头。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.0.iter().any(|net| net.contains(ip))
+    }
+
+    /// 客户端地址
+    ///
+    /// 对端地址来自 `ConnectInfo<SocketAddr>`，未提供时无法判断转发头是否可信，返回空。
+    /// 转发头全部由可信代理添加时取最左边的地址，没有 `X-Forwarded-For` 时使用 `X-Real-IP`。
+    pub fn client_ip(&self, headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+        let ConnectInfo(peer) = extensions.get::<ConnectInfo<SocketAddr>>()?;
+        let peer = peer.ip().to_canonical();
+        if !self.contains(peer) {
+            return Some(peer);
+        }
+
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        if forwarded.is_empty() {
+            return headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<IpAddr>().ok())
+                .map(|ip| ip.to_canonical())
+                .or(Some(peer));
+        }
+
+        let mut client = peer;
+        for hop in forwarded.iter().rev() {
+            // 无法解析的地址之后的内容都不可信，停在上一个可信代理
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip.to_canonical();
+            if !self.contains(client) {
+                break;
+            }
+        }
+        Some(client)
+    }
+}
+
+impl FromStr for TrustedProxies {
+    type Err = String;
+
+    /// 逗号分隔的地址或网段，空字符串表示不信任任何代理
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|net| !net.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(TrustedProxies)
+    }
+}
+
+/// 地址或网段，单个地址视为最长前缀的网段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid address or network `{s}`");
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| invalid())?
+            .to_canonical();
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(invalid)?,
+            None => max,
+        };
+        Ok(IpNet { addr, prefix })
+    }
+}
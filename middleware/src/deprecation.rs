@@ -0,0 +1,94 @@
+//! 接口弃用标记
+//!
+//! 被标记的路由在响应中加上 `Deprecation`（RFC 9745）和 `Sunset`（RFC 8594）头，
+//! 配置了新版本前缀时再加上指向新版本的 `Link: <...>; rel="successor-version"`：
+//!
+//! ```ignore
+//! let deprecation = Deprecation::new(deprecated_at)
+//!     .sunset(sunset_at)
+//!     .successor("/api/v2");
+//! get(handler).route_layer(from_fn_with_state(deprecation, deprecated))
+//! ```
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+pub const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+    deprecated_at: SystemTime,
+    sunset: Option<SystemTime>,
+    successor: Option<String>,
+}
+
+impl Deprecation {
+    /// `deprecated_at` 为开始弃用的时间
+    pub fn new(deprecated_at: SystemTime) -> Self {
+        Self {
+            deprecated_at,
+            sunset: None,
+            successor: None,
+        }
+    }
+
+    /// 接口计划下线的时间
+    pub fn sunset(mut self, at: SystemTime) -> Self {
+        self.sunset = Some(at);
+        self
+    }
+
+    /// 新版本的路径前缀，例如 `/api/v2`，与当前请求路径拼接后作为后继版本的地址
+    pub fn successor(mut self, prefix: impl Into<String>) -> Self {
+        self.successor = Some(prefix.into());
+        self
+    }
+
+    /// `Deprecation` 头的值，格式为 `@` 加 Unix 时间戳
+    fn deprecation_value(&self) -> HeaderValue {
+        let secs = self
+            .deprecated_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        HeaderValue::from_str(&format!("@{secs}")).expect("timestamp is a valid header value")
+    }
+}
+
+/// 为响应加上弃用相关的头
+///
+/// 在嵌套路由中请求路径已去掉版本前缀，直接拼在新版本前缀之后即可。
+pub async fn deprecated(
+    State(deprecation): State<Deprecation>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let successor = deprecation.successor.as_ref().and_then(|prefix| {
+        let path = req
+            .uri()
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/");
+        HeaderValue::from_str(&format!("<{prefix}{path}>; rel=\"successor-version\"")).ok()
+    });
+
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION, deprecation.deprecation_value());
+    if let Some(sunset) = deprecation.sunset {
+        // HTTP 日期只包含 ASCII 字符，不会构造失败
+        if let Ok(value) = HeaderValue::from_str(&httpdate::fmt_http_date(sunset)) {
+            headers.insert(SUNSET, value);
+        }
+    }
+    if let Some(link) = successor {
+        headers.append(header::LINK, link);
+    }
+    response
+}
@@ -0,0 +1,271 @@
+use crate::axum::UserInfo;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode, errors::ErrorKind,
+    get_current_timestamp,
+};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+
+/// HS256 密钥的最小长度（字节）
+const MIN_SECRET_LEN: usize = 32;
+
+/// JWT 载荷
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// 用户 ID
+    pub sub: String,
+    pub name: String,
+    pub role: String,
+    pub iat: u64,
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+}
+
+impl TryFrom<Claims> for UserInfo {
+    type Error = JwtError;
+
+    fn try_from(claims: Claims) -> Result<Self, Self::Error> {
+        let user_id = claims
+            .sub
+            .parse()
+            .map_err(|_| JwtError::InvalidToken("Invalid subject claim".to_string()))?;
+        Ok(UserInfo {
+            user_id,
+            username: claims.name,
+            role: claims.role,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum JwtError {
+    /// 配置缺失或非法
+    Config(String),
+    /// 令牌已过期
+    Expired,
+    /// 令牌签名、签发者、受众等校验失败
+    InvalidToken(String),
+    /// 签发令牌失败
+    Encode(jsonwebtoken::errors::Error),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtError::Config(msg) => write!(f, "Invalid JWT configuration: {msg}"),
+            JwtError::Expired => write!(f, "Token has expired"),
+            JwtError::InvalidToken(msg) => write!(f, "{msg}"),
+            JwtError::Encode(e) => write!(f, "Failed to encode token: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for JwtError {}
+
+impl From<jsonwebtoken::errors::Error> for JwtError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        match e.kind() {
+            ErrorKind::ExpiredSignature => JwtError::Expired,
+            ErrorKind::InvalidIssuer => JwtError::InvalidToken("Invalid token issuer".to_string()),
+            ErrorKind::InvalidAudience => {
+                JwtError::InvalidToken("Invalid token audience".to_string())
+            }
+            ErrorKind::ImmatureSignature => {
+                JwtError::InvalidToken("Token is not yet valid".to_string())
+            }
+            _ => JwtError::InvalidToken("Invalid token".to_string()),
+        }
+    }
+}
+
+/// 签名密钥
+#[derive(Clone)]
+pub enum JwtKey {
+    /// HS256 共享密钥
+    Secret(Vec<u8>),
+    /// RS256 PEM 密钥对，只做校验的服务可以不配置私钥
+    Rsa {
+        private_pem: Option<Vec<u8>>,
+        public_pem: Vec<u8>,
+    },
+}
+
+/// JWT 配置
+#[derive(Clone)]
+pub struct JwtConfig {
+    pub key: JwtKey,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    /// 访问令牌有效期（秒）
+    pub expires_in: u64,
+    /// 刷新令牌有效期（秒）
+    pub refresh_expires_in: u64,
+    /// 校验 exp/nbf 时允许的时钟偏差（秒）
+    pub leeway: u64,
+}
+
+impl JwtConfig {
+    /// 从环境变量读取配置
+    ///
+    /// - `JWT_ALGORITHM`: `HS256`（默认）或 `RS256`
+    /// - `JWT_SECRET`: HS256 密钥
+    /// - `JWT_PRIVATE_KEY` / `JWT_PUBLIC_KEY`: RS256 PEM 文件路径
+    /// - `JWT_ISSUER` / `JWT_AUDIENCE`: 可选的签发者与受众
+    /// - `JWT_EXPIRES_IN`: 访问令牌有效期，默认 900 秒
+    /// - `JWT_REFRESH_EXPIRES_IN`: 刷新令牌有效期，默认 14 天
+    /// - `JWT_LEEWAY`: 时钟偏差，默认 60 秒
+    pub fn from_env() -> Result<Self, JwtError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// 通过 `var` 按键名读取配置，键名与 `from_env` 相同，空值视为未设置
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, JwtError> {
+        let var = |key: &str| var(key).filter(|v| !v.is_empty());
+        let algorithm = var("JWT_ALGORITHM").unwrap_or_else(|| "HS256".to_string());
+        let key = match algorithm.to_uppercase().as_str() {
+            "HS256" => JwtKey::Secret(
+                var("JWT_SECRET")
+                    .ok_or_else(|| JwtError::Config("JWT_SECRET is not set".to_string()))?
+                    .into_bytes(),
+            ),
+            "RS256" => {
+                let public_path = var("JWT_PUBLIC_KEY")
+                    .ok_or_else(|| JwtError::Config("JWT_PUBLIC_KEY is not set".to_string()))?;
+                let private_pem = var("JWT_PRIVATE_KEY").map(read_pem).transpose()?;
+                JwtKey::Rsa {
+                    private_pem,
+                    public_pem: read_pem(public_path)?,
+                }
+            }
+            other => {
+                return Err(JwtError::Config(format!(
+                    "Unsupported JWT_ALGORITHM: {other}"
+                )));
+            }
+        };
+        let secs = |key: &str, default: u64| match var(key) {
+            Some(v) => v
+                .parse()
+                .map_err(|_| JwtError::Config(format!("{key} must be a number of seconds"))),
+            None => Ok(default),
+        };
+
+        Ok(JwtConfig {
+            key,
+            issuer: var("JWT_ISSUER"),
+            audience: var("JWT_AUDIENCE"),
+            expires_in: secs("JWT_EXPIRES_IN", 900)?,
+            refresh_expires_in: secs("JWT_REFRESH_EXPIRES_IN", 14 * 24 * 3600)?,
+            leeway: secs("JWT_LEEWAY", 60)?,
+        })
+    }
+}
+
+fn read_pem(path: String) -> Result<Vec<u8>, JwtError> {
+    fs::read(&path).map_err(|e| JwtError::Config(format!("Failed to read {path}: {e}")))
+}
+
+/// 由配置构建的签发/校验密钥，在中间件和登录接口间共享
+#[derive(Clone)]
+pub struct JwtKeys {
+    header: Header,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    validation: Validation,
+    issuer: Option<String>,
+    audience: Option<String>,
+    expires_in: u64,
+    refresh_expires_in: u64,
+}
+
+impl JwtKeys {
+    pub fn new(config: &JwtConfig) -> Result<Self, JwtError> {
+        let (algorithm, encoding, decoding) = match &config.key {
+            JwtKey::Secret(secret) => {
+                if secret.len() < MIN_SECRET_LEN {
+                    return Err(JwtError::Config(format!(
+                        "JWT_SECRET must be at least {MIN_SECRET_LEN} bytes"
+                    )));
+                }
+                (
+                    Algorithm::HS256,
+                    Some(EncodingKey::from_secret(secret)),
+                    DecodingKey::from_secret(secret),
+                )
+            }
+            JwtKey::Rsa {
+                private_pem,
+                public_pem,
+            } => {
+                let encoding = private_pem
+                    .as_deref()
+                    .map(EncodingKey::from_rsa_pem)
+                    .transpose()
+                    .map_err(|e| JwtError::Config(format!("Invalid RSA private key: {e}")))?;
+                let decoding = DecodingKey::from_rsa_pem(public_pem)
+                    .map_err(|e| JwtError::Config(format!("Invalid RSA public key: {e}")))?;
+                (Algorithm::RS256, encoding, decoding)
+            }
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = config.leeway;
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(issuer) = &config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &config.audience {
+            validation.set_audience(&[audience]);
+        }
+
+        Ok(JwtKeys {
+            header: Header::new(algorithm),
+            encoding,
+            decoding,
+            validation,
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            expires_in: config.expires_in,
+            refresh_expires_in: config.refresh_expires_in,
+        })
+    }
+
+    /// 访问令牌有效期（秒）
+    pub fn expires_in(&self) -> u64 {
+        self.expires_in
+    }
+
+    /// 刷新令牌有效期（秒）
+    pub fn refresh_expires_in(&self) -> u64 {
+        self.refresh_expires_in
+    }
+
+    /// 为用户签发访问令牌
+    pub fn encode(&self, user: &UserInfo) -> Result<String, JwtError> {
+        let encoding = self
+            .encoding
+            .as_ref()
+            .ok_or_else(|| JwtError::Config("No signing key configured".to_string()))?;
+        let now = get_current_timestamp();
+        let claims = Claims {
+            sub: user.user_id.to_string(),
+            name: user.username.clone(),
+            role: user.role.clone(),
+            iat: now,
+            exp: now + self.expires_in,
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+        };
+        encode(&self.header, &claims, encoding).map_err(JwtError::Encode)
+    }
+
+    /// 校验令牌签名、有效期、签发者和受众
+    pub fn decode(&self, token: &str) -> Result<Claims, JwtError> {
+        Ok(decode::<Claims>(token, &self.decoding, &self.validation)?.claims)
+    }
+}
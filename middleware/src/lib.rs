@@ -1,2 +1,9 @@
 pub mod axum;
+pub mod context;
+pub mod deprecation;
+pub mod jwt;
+pub mod metrics;
+pub mod session;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod tower;
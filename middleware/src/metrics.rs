@@ -0,0 +1,240 @@
+//! Prometheus 指标
+//!
+//! `MetricsLayer` 按请求方法、匹配到的路由模板和状态码统计请求数、耗时和处理中的请求数，
+//! 路由使用 `MatchedPath`（如 `/api/v1/posts/{id}`）而不是实际路径，避免标签值无限增长。
+//! 数据库查询耗时和连接池状态由应用通过 `observe_query` / `set_pool` 上报。
+
+use axum::extract::MatchedPath;
+use http::{Request, Response};
+use pin_project_lite::pin_project;
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+/// `/metrics` 响应的 Content-Type
+pub const CONTENT_TYPE: &str = prometheus::TEXT_FORMAT;
+
+/// 没有匹配到路由的请求使用的路由标签
+const UNMATCHED: &str = "unmatched";
+
+/// 数据库查询耗时的分桶（秒），比 HTTP 请求的默认分桶更细
+const QUERY_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// 应用的全部指标，克隆后共享同一个注册表
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    in_flight: IntGaugeVec,
+    query_duration: HistogramVec,
+    pool_connections: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests handled"),
+            &["method", "route", "status"],
+        )?;
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route", "status"],
+        )?;
+        let in_flight = IntGaugeVec::new(
+            Opts::new(
+                "http_requests_in_flight",
+                "Number of HTTP requests being handled",
+            ),
+            &["method", "route"],
+        )?;
+        let query_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "db_query_duration_seconds",
+                "Database query duration in seconds",
+            )
+            .buckets(QUERY_BUCKETS.to_vec()),
+            &["operation", "outcome"],
+        )?;
+        let pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_connections",
+                "Number of database pool connections by state",
+            ),
+            &["state"],
+        )?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(request_duration.clone()))?;
+        registry.register(Box::new(in_flight.clone()))?;
+        registry.register(Box::new(query_duration.clone()))?;
+        registry.register(Box::new(pool_connections.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            requests,
+            request_duration,
+            in_flight,
+            query_duration,
+            pool_connections,
+        })
+    }
+
+    /// 统计 HTTP 请求的中间件层
+    pub fn layer(&self) -> MetricsLayer {
+        MetricsLayer {
+            metrics: self.clone(),
+        }
+    }
+
+    /// 记录一次数据库查询，按 SQL 的第一个关键字区分操作类型
+    pub fn observe_query(&self, sql: &str, elapsed: Duration, failed: bool) {
+        let keyword = sql.split_whitespace().next().unwrap_or_default();
+        let operation = ["SELECT", "INSERT", "UPDATE", "DELETE"]
+            .into_iter()
+            .find(|op| keyword.eq_ignore_ascii_case(op))
+            .unwrap_or("OTHER");
+        let outcome = if failed { "error" } else { "ok" };
+        self.query_duration
+            .with_label_values(&[operation, outcome])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// 更新连接池状态，`size` 为已建立的连接数，`idle` 为其中空闲的连接数
+    pub fn set_pool(&self, size: u32, idle: usize) {
+        let idle = idle as i64;
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections
+            .with_label_values(&["active"])
+            .set(i64::from(size) - idle);
+    }
+
+    /// 以 Prometheus 文本格式输出全部指标
+    pub fn render(&self) -> Result<String, prometheus::Error> {
+        TextEncoder::new().encode_to_string(&self.registry.gather())
+    }
+}
+
+/// 统计 HTTP 请求的中间件层，由 `Metrics::layer` 创建
+///
+/// 需要通过 `Router::layer` 添加，才能读取到 `MatchedPath`。
+#[derive(Clone)]
+pub struct MetricsLayer {
+    metrics: Metrics,
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: Metrics,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for MetricsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let method = request.method().to_string();
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(UNMATCHED, MatchedPath::as_str)
+            .to_string();
+        let in_flight = self.metrics.in_flight.with_label_values(&[&method, &route]);
+        in_flight.inc();
+
+        ResponseFuture {
+            future: self.inner.call(request),
+            request: InFlight {
+                metrics: self.metrics.clone(),
+                method,
+                route,
+                start: Instant::now(),
+                gauge: in_flight,
+            },
+        }
+    }
+}
+
+pin_project! {
+    /// [`MetricsService`] 的响应 future
+    pub struct ResponseFuture<F> {
+        #[pin]
+        future: F,
+        request: InFlight,
+    }
+}
+
+impl<F, ResBody, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.future.poll(cx));
+        let status = match &result {
+            Ok(res) => res.status().as_str().to_string(),
+            Err(_) => "error".to_string(),
+        };
+        this.request.finish(&status);
+        Poll::Ready(result)
+    }
+}
+
+/// 处理中的请求，释放时减少处理中的请求数，请求被取消时也一样
+struct InFlight {
+    metrics: Metrics,
+    method: String,
+    route: String,
+    start: Instant,
+    gauge: IntGauge,
+}
+
+impl InFlight {
+    fn finish(&self, status: &str) {
+        let labels = [self.method.as_str(), self.route.as_str(), status];
+        self.metrics.requests.with_label_values(&labels).inc();
+        self.metrics
+            .request_duration
+            .with_label_values(&labels)
+            .observe(self.start.elapsed().as_secs_f64());
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
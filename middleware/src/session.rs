@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions, aio::MultiplexedConnection};
+use std::fmt;
+use tower_sessions::{
+    MemoryStore, SessionStore,
+    session::{Id, Record},
+    session_store,
+};
+
+/// 会话中保存登录用户 ID 的键，角色等信息每次请求重新读取，见 `axum::UserLoader`
+pub const SESSION_USER_KEY: &str = "user";
+
+/// 基于 Redis 的会话存储
+///
+/// 每个会话以 JSON 形式保存在 `{prefix}{id}` 键下，并随会话一起过期。
+#[derive(Clone)]
+pub struct RedisStore {
+    conn: MultiplexedConnection,
+    prefix: String,
+}
+
+impl fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+impl RedisStore {
+    pub async fn connect(url: &str) -> redis::RedisResult<Self> {
+        let client = redis::Client::open(url)?;
+        let conn = client.get_multiplexed_async_connection().await?;
+        Ok(Self {
+            conn,
+            prefix: "session:".to_string(),
+        })
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// 发送 PING 检查连接是否可用
+    pub async fn ping(&self) -> redis::RedisResult<()> {
+        let mut conn = self.conn.clone();
+        redis::cmd("PING").query_async::<String>(&mut conn).await?;
+        Ok(())
+    }
+
+    fn key(&self, id: &Id) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+
+    /// 写入会话记录，返回是否写入成功（NX 条件不满足时为 false）
+    async fn set(
+        &self,
+        record: &Record,
+        existence: Option<ExistenceCheck>,
+    ) -> session_store::Result<bool> {
+        let value =
+            serde_json::to_vec(record).map_err(|e| session_store::Error::Encode(e.to_string()))?;
+        let expire_at = record.expiry_date.unix_timestamp().max(0) as u64;
+
+        let mut options = SetOptions::default().with_expiration(SetExpiry::EXAT(expire_at));
+        if let Some(existence) = existence {
+            options = options.conditional_set(existence);
+        }
+
+        let mut conn = self.conn.clone();
+        let reply: Option<String> = conn
+            .set_options(self.key(&record.id), value, options)
+            .await
+            .map_err(backend_error)?;
+        Ok(reply.is_some())
+    }
+}
+
+fn backend_error(e: redis::RedisError) -> session_store::Error {
+    session_store::Error::Backend(e.to_string())
+}
+
+#[async_trait]
+impl SessionStore for RedisStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        // ID 冲突时重新生成
+        while !self.set(record, Some(ExistenceCheck::NX)).await? {
+            record.id = Id::default();
+        }
+        Ok(())
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        self.set(record, None).await?;
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        let mut conn = self.conn.clone();
+        let value: Option<Vec<u8>> = conn
+            .get(self.key(session_id))
+            .await
+            .map_err(backend_error)?;
+
+        value
+            .map(|value| {
+                serde_json::from_slice(&value)
+                    .map_err(|e| session_store::Error::Decode(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .del(self.key(session_id))
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+/// 应用使用的会话存储
+///
+/// 配置了 `REDIS_URL` 时使用 Redis，否则（例如测试中）退回到进程内存储。
+#[derive(Debug, Clone)]
+pub enum AppSessionStore {
+    Redis(RedisStore),
+    Memory(MemoryStore),
+}
+
+#[async_trait]
+impl SessionStore for AppSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        match self {
+            AppSessionStore::Redis(store) => store.create(record).await,
+            AppSessionStore::Memory(store) => store.create(record).await,
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        match self {
+            AppSessionStore::Redis(store) => store.save(record).await,
+            AppSessionStore::Memory(store) => store.save(record).await,
+        }
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        match self {
+            AppSessionStore::Redis(store) => store.load(session_id).await,
+            AppSessionStore::Memory(store) => store.load(session_id).await,
+        }
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        match self {
+            AppSessionStore::Redis(store) => store.delete(session_id).await,
+            AppSessionStore::Memory(store) => store.delete(session_id).await,
+        }
+    }
+}
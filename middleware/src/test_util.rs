@@ -0,0 +1,64 @@
+//! 测试辅助，开启 `test-util` 特性后可用
+
+use crate::axum::{AuthState, UserInfo, UserLoader};
+use crate::jwt::{JwtConfig, JwtKey, JwtKeys};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+impl JwtConfig {
+    /// 测试用的 HS256 配置：固定密钥，不校验签发者和受众，不允许时钟偏差
+    pub fn for_tests() -> Self {
+        JwtConfig {
+            key: JwtKey::Secret(b"0123456789abcdef0123456789abcdef".to_vec()),
+            issuer: None,
+            audience: None,
+            expires_in: 900,
+            refresh_expires_in: 3600,
+            leeway: 0,
+        }
+    }
+}
+
+impl JwtKeys {
+    /// 由 `JwtConfig::for_tests` 构建的密钥
+    pub fn for_tests() -> Self {
+        JwtKeys::new(&JwtConfig::for_tests()).expect("Invalid test JWT config")
+    }
+}
+
+impl AuthState {
+    /// 使用 `JwtKeys::for_tests` 的密钥，会话用户从 `users` 中读取
+    pub fn for_tests(users: MemoryUsers) -> Self {
+        AuthState {
+            keys: Arc::new(JwtKeys::for_tests()),
+            users: Arc::new(users),
+        }
+    }
+}
+
+/// 内存中的用户表，克隆后共享同一份数据，用于模拟修改角色和删除用户
+#[derive(Clone, Default)]
+pub struct MemoryUsers(Arc<Mutex<HashMap<u32, UserInfo>>>);
+
+impl MemoryUsers {
+    /// 添加或替换用户
+    pub fn insert(&self, user: UserInfo) {
+        self.0.lock().unwrap().insert(user.user_id, user);
+    }
+
+    pub fn remove(&self, user_id: u32) {
+        self.0.lock().unwrap().remove(&user_id);
+    }
+}
+
+#[async_trait]
+impl UserLoader for MemoryUsers {
+    async fn load_user(
+        &self,
+        user_id: u32,
+    ) -> Result<Option<UserInfo>, Box<dyn Error + Send + Sync>> {
+        Ok(self.0.lock().unwrap().get(&user_id).cloned())
+    }
+}
@@ -1,18 +1,32 @@
+pub use crate::context::X_REQUEST_ID;
+use crate::context::{RequestContext, TrustedProxies};
 use http::{HeaderValue, Request, Response};
 use pin_project_lite::pin_project;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::task::futures::TaskLocalFuture;
 use tower::{Layer, Service};
 
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// 当前请求的 ID，只能在 `LoggingService` 处理的请求中取到
+///
+/// 用于构造错误响应等拿不到请求本身的地方。
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
 pin_project! {
     /// Response future for [`CookieManager`].
     #[derive(Debug)]
     pub struct ResponseFuture<F> {
         #[pin]
-        pub(crate) future: F,
-        pub(crate) data: SharedData,
+        pub(crate) future: TaskLocalFuture<String, F>,
+        pub(crate) request_id: String,
     }
 }
 
@@ -25,46 +39,25 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
         let mut res = std::task::ready!(this.future.poll(cx)?);
-        res.headers_mut().insert(
-            "tower-Request-ID",
-            HeaderValue::from_str(&this.data.request_id).unwrap(),
-        );
+        // 请求 ID 只含可见 ASCII 字符，不会转换失败
+        if let Ok(value) = HeaderValue::from_str(this.request_id) {
+            res.headers_mut().insert(X_REQUEST_ID.clone(), value);
+        }
 
         Poll::Ready(Ok(res))
     }
 }
 
-// 定义共享数据类型
-#[derive(Clone, Debug)]
-pub struct SharedData {
-    pub user_id: Option<String>,
-    pub request_id: String,
-}
-
-impl Default for SharedData {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl SharedData {
-    pub fn new() -> Self {
-        Self {
-            user_id: None,
-            // 使用更高效的时间戳作为请求ID
-            request_id: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_nanos()
-                .to_string(),
-        }
-    }
-}
-
-// 简单日志中间件
+// 请求 ID 中间件
+//
+// 创建请求上下文 `RequestContext` 放入请求扩展，其中的请求 ID 沿用上游传入的
+// `X-Request-ID`，没有时生成新的，同时写入请求头并在响应头中返回，
+// 处理请求期间可以通过 `current_request_id` 读取。
+// 需要放在 `TraceLayer` 外层，请求 span 才能记录请求 ID。
 #[derive(Clone)]
 pub struct LoggingService<S> {
     inner: S,
+    proxies: Arc<TrustedProxies>,
 }
 
 impl<S, R> Service<Request<R>> for LoggingService<S>
@@ -81,28 +74,40 @@ where
     }
 
     fn call(&mut self, mut request: Request<R>) -> Self::Future {
-        // 创建共享数据并获取请求ID
-        let mut shared_data = SharedData::new();
-        shared_data.request_id = "111".to_string();
+        // 创建请求上下文并获取请求ID
+        let context = RequestContext::new(request.headers(), request.extensions(), &self.proxies);
+        let request_id = context.request_id.clone();
 
-        // 将共享数据添加到请求扩展中
-        request.extensions_mut().insert(shared_data.clone());
+        // 将请求ID写回请求头，内层服务和转发的请求都使用同一个ID
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            request.headers_mut().insert(X_REQUEST_ID.clone(), value);
+        }
+        // 将请求上下文添加到请求扩展中
+        request.extensions_mut().insert(context);
 
         // 创建响应 future
         ResponseFuture {
-            future: self.inner.call(request),
-            data: shared_data,
+            future: REQUEST_ID.scope(request_id.clone(), self.inner.call(request)),
+            request_id,
         }
     }
 }
 
 // 日志中间件层
 #[derive(Clone, Default)]
-pub struct LoggingLayer;
+pub struct LoggingLayer {
+    proxies: Arc<TrustedProxies>,
+}
 
 impl LoggingLayer {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// 可信的反向代理，只有来自这些地址的请求才从转发头中读取客户端地址
+    pub fn trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.proxies = Arc::new(proxies);
+        self
     }
 }
 
@@ -110,7 +115,10 @@ impl<S> Layer<S> for LoggingLayer {
     type Service = LoggingService<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        LoggingService { inner }
+        LoggingService {
+            inner,
+            proxies: self.proxies.clone(),
+        }
     }
 }
 
@@ -118,6 +126,9 @@ impl<S> Layer<S> for LoggingService<S> {
     type Service = LoggingService<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        LoggingService { inner }
+        LoggingService {
+            inner,
+            proxies: self.proxies.clone(),
+        }
     }
 }
@@ -0,0 +1,157 @@
+use axum::{
+    Extension, Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+    middleware::from_fn_with_state,
+    routing::get,
+};
+use middleware::axum::{AuthState, RequestContext, UserInfo, auth};
+use middleware::jwt::JwtKeys;
+use middleware::session::{AppSessionStore, SESSION_USER_KEY};
+use middleware::test_util::MemoryUsers;
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, Session, SessionManagerLayer};
+
+fn user() -> UserInfo {
+    UserInfo {
+        user_id: 7,
+        username: "alice".to_string(),
+        role: "user".to_string(),
+    }
+}
+
+async fn login(session: Session) -> StatusCode {
+    session
+        .insert(SESSION_USER_KEY, user().user_id)
+        .await
+        .unwrap();
+    StatusCode::OK
+}
+
+async fn me(Extension(ctx): Extension<RequestContext>) -> Result<String, StatusCode> {
+    ctx.user_info
+        .map(|user| user.username)
+        .ok_or(StatusCode::UNAUTHORIZED)
+}
+
+fn app(users: MemoryUsers) -> Router {
+    let store = AppSessionStore::Memory(MemoryStore::default());
+    Router::new()
+        .route("/login", get(login))
+        .route("/me", get(me))
+        .layer(from_fn_with_state(AuthState::for_tests(users), auth))
+        .layer(SessionManagerLayer::new(store).with_secure(false))
+}
+
+#[tokio::test]
+async fn bearer_token() {
+    let token = JwtKeys::for_tests().encode(&user()).unwrap();
+
+    let response = app(MemoryUsers::default())
+        .oneshot(
+            Request::get("/me")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["axum-User-ID"], "7");
+}
+
+#[tokio::test]
+async fn invalid_bearer_token() {
+    let response = app(MemoryUsers::default())
+        .oneshot(
+            Request::get("/me")
+                .header(header::AUTHORIZATION, "Bearer not-a-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+}
+
+#[tokio::test]
+async fn anonymous_request() {
+    let response = app(MemoryUsers::default())
+        .oneshot(Request::get("/me").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(!response.headers().contains_key(header::WWW_AUTHENTICATE));
+}
+
+/// 登录并返回会话 Cookie
+async fn session_cookie(app: &Router) -> String {
+    let response = app
+        .clone()
+        .oneshot(Request::get("/login").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    response.headers()[header::SET_COOKIE]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+async fn me_with(app: &Router, cookie: &str) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::get("/me")
+                .header(header::COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn session_user() {
+    let users = MemoryUsers::default();
+    users.insert(user());
+    let app = app(users);
+    let cookie = session_cookie(&app).await;
+
+    let response = me_with(&app, &cookie).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["axum-Username"], "alice");
+    assert_eq!(response.headers()["axum-User-Role"], "user");
+}
+
+#[tokio::test]
+async fn session_reloads_role_and_deleted_user() {
+    let users = MemoryUsers::default();
+    users.insert(UserInfo {
+        role: "admin".to_string(),
+        ..user()
+    });
+    let app = app(users.clone());
+    let cookie = session_cookie(&app).await;
+
+    let response = me_with(&app, &cookie).await;
+    assert_eq!(response.headers()["axum-User-Role"], "admin");
+
+    // 修改角色后，已有的会话使用新的角色
+    users.insert(user());
+    let response = me_with(&app, &cookie).await;
+    assert_eq!(response.headers()["axum-User-Role"], "user");
+
+    // 删除用户后会话失效
+    users.remove(user().user_id);
+    let response = me_with(&app, &cookie).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    users.insert(user());
+    let response = me_with(&app, &cookie).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,142 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{Request, header},
+    middleware::from_fn_with_state,
+    routing::get,
+};
+use middleware::axum::{AuthState, UserInfo, auth};
+use middleware::context::{RequestContext, TrustedProxies};
+use middleware::jwt::JwtKeys;
+use middleware::test_util::MemoryUsers;
+use middleware::tower::{LoggingLayer, X_REQUEST_ID};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+async fn describe(ctx: RequestContext) -> String {
+    format!(
+        "{} {} {}",
+        ctx.request_id,
+        ctx.user_info.map(|user| user.username).unwrap_or_default(),
+        ctx.client_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+    )
+}
+
+fn app() -> Router {
+    let proxies = "127.0.0.1, 10.0.0.0/8".parse().unwrap();
+    Router::new()
+        .route("/ctx", get(describe))
+        .layer(from_fn_with_state(
+            AuthState::for_tests(MemoryUsers::default()),
+            auth,
+        ))
+        .layer(LoggingLayer::new().trusted_proxies(proxies))
+}
+
+fn connect(mut request: Request<Body>, peer: &str) -> Request<Body> {
+    let peer: SocketAddr = peer.parse().unwrap();
+    request.extensions_mut().insert(ConnectInfo(peer));
+    request
+}
+
+async fn call(request: Request<Body>) -> String {
+    let response = app().oneshot(request).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn auth_fills_the_same_context() {
+    let token = JwtKeys::for_tests()
+        .encode(&UserInfo {
+            user_id: 7,
+            username: "alice".to_string(),
+            role: "user".to_string(),
+        })
+        .unwrap();
+    let request = Request::get("/ctx")
+        .header(&X_REQUEST_ID, "req-7")
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        // 最左边的地址由客户端填写，可以伪造
+        .header("x-forwarded-for", "198.51.100.1, 203.0.113.9, 10.0.0.1")
+        .body(Body::empty())
+        .unwrap();
+
+    assert_eq!(
+        call(connect(request, "10.0.0.2:5000")).await,
+        "req-7 alice 203.0.113.9"
+    );
+}
+
+#[tokio::test]
+async fn forwarded_headers_only_trusted_from_proxies() {
+    let request = || {
+        Request::get("/ctx")
+            .header(&X_REQUEST_ID, "req-1")
+            .header("x-forwarded-for", "203.0.113.9")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    assert_eq!(
+        call(connect(request(), "127.0.0.1:5000")).await,
+        "req-1  203.0.113.9"
+    );
+    // 不在可信代理中的内网地址也不信任
+    assert_eq!(
+        call(connect(request(), "192.168.1.4:5000")).await,
+        "req-1  192.168.1.4"
+    );
+    assert_eq!(
+        call(connect(request(), "198.51.100.4:5000")).await,
+        "req-1  198.51.100.4"
+    );
+    // 没有对端地址时无法判断转发头是否可信
+    assert_eq!(call(request()).await, "req-1  ");
+}
+
+#[test]
+fn client_ip_skips_trusted_hops_from_the_right() {
+    let proxies: TrustedProxies = "10.0.0.0/8, ::1".parse().unwrap();
+    let client_ip = |peer: &str, forwarded: &[&str]| {
+        let mut request = Request::get("/").body(()).unwrap();
+        for value in forwarded {
+            request
+                .headers_mut()
+                .append("x-forwarded-for", value.parse().unwrap());
+        }
+        let peer: SocketAddr = peer.parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer));
+        proxies
+            .client_ip(request.headers(), request.extensions())
+            .map(|ip| ip.to_string())
+    };
+
+    // 多个请求头按顺序拼接
+    assert_eq!(
+        client_ip("10.1.1.1:80", &["1.1.1.1, 2.2.2.2", "10.0.0.5"]).as_deref(),
+        Some("2.2.2.2")
+    );
+    // 全部是可信代理时取最左边的地址
+    assert_eq!(
+        client_ip("[::1]:80", &["10.0.0.7, 10.0.0.5"]).as_deref(),
+        Some("10.0.0.7")
+    );
+    // 无法解析的地址之前的内容不再采用
+    assert_eq!(
+        client_ip("10.1.1.1:80", &["1.1.1.1, garbage, 10.0.0.5"]).as_deref(),
+        Some("10.0.0.5")
+    );
+    // IPv4 映射的 IPv6 对端按 IPv4 匹配
+    assert_eq!(
+        client_ip("[::ffff:10.0.0.1]:80", &["2.2.2.2"]).as_deref(),
+        Some("2.2.2.2")
+    );
+
+    assert_eq!("".parse::<TrustedProxies>(), Ok(TrustedProxies::default()));
+    assert!("10.0.0.0/33".parse::<TrustedProxies>().is_err());
+    assert!("proxy.local".parse::<TrustedProxies>().is_err());
+}
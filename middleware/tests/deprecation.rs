@@ -0,0 +1,54 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, header},
+    middleware::from_fn_with_state,
+    routing::get,
+};
+use middleware::deprecation::{DEPRECATION, Deprecation, SUNSET, deprecated};
+use std::time::{Duration, UNIX_EPOCH};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    let deprecation = Deprecation::new(UNIX_EPOCH + Duration::from_secs(1_767_225_600))
+        .sunset(UNIX_EPOCH + Duration::from_secs(1_782_864_000))
+        .successor("/api/v2");
+    let v1 = Router::new()
+        .route(
+            "/posts/{id}",
+            get(|| async { "old" }).route_layer(from_fn_with_state(deprecation, deprecated)),
+        )
+        .route("/posts", get(|| async { "list" }));
+    Router::new().nest("/api/v1", v1)
+}
+
+#[tokio::test]
+async fn deprecated_route_has_headers() {
+    let response = app()
+        .oneshot(
+            Request::get("/api/v1/posts/1?x=1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let headers = response.headers();
+    assert_eq!(headers[DEPRECATION], "@1767225600");
+    assert_eq!(headers[SUNSET], "Wed, 01 Jul 2026 00:00:00 GMT");
+    assert_eq!(
+        headers[header::LINK],
+        "</api/v2/posts/1?x=1>; rel=\"successor-version\""
+    );
+}
+
+#[tokio::test]
+async fn other_routes_are_untouched() {
+    let response = app()
+        .oneshot(Request::get("/api/v1/posts").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert!(response.headers().get(DEPRECATION).is_none());
+    assert!(response.headers().get(SUNSET).is_none());
+}
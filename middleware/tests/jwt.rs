@@ -0,0 +1,84 @@
+use middleware::axum::UserInfo;
+use middleware::jwt::{JwtConfig, JwtError, JwtKey, JwtKeys};
+
+fn config() -> JwtConfig {
+    JwtConfig {
+        issuer: Some("rs-web".to_string()),
+        audience: Some("rs-web-api".to_string()),
+        ..JwtConfig::for_tests()
+    }
+}
+
+fn user() -> UserInfo {
+    UserInfo {
+        user_id: 7,
+        username: "Alice".to_string(),
+        role: "admin".to_string(),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let keys = JwtKeys::new(&config()).unwrap();
+    let token = keys.encode(&user()).unwrap();
+
+    let claims = keys.decode(&token).unwrap();
+    let decoded = UserInfo::try_from(claims).unwrap();
+    assert_eq!(decoded.user_id, 7);
+    assert_eq!(decoded.username, "Alice");
+    assert_eq!(decoded.role, "admin");
+}
+
+#[test]
+fn rejects_expired_token() {
+    let mut expired = config();
+    expired.expires_in = 0;
+    let keys = JwtKeys::new(&expired).unwrap();
+    let token = keys.encode(&user()).unwrap();
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    assert!(matches!(keys.decode(&token), Err(JwtError::Expired)));
+}
+
+#[test]
+fn rejects_foreign_issuer_and_audience() {
+    let keys = JwtKeys::new(&config()).unwrap();
+
+    let mut other = config();
+    other.issuer = Some("someone-else".to_string());
+    let token = JwtKeys::new(&other).unwrap().encode(&user()).unwrap();
+    assert!(matches!(
+        keys.decode(&token),
+        Err(JwtError::InvalidToken(_))
+    ));
+
+    let mut other = config();
+    other.audience = Some("another-api".to_string());
+    let token = JwtKeys::new(&other).unwrap().encode(&user()).unwrap();
+    assert!(matches!(
+        keys.decode(&token),
+        Err(JwtError::InvalidToken(_))
+    ));
+}
+
+#[test]
+fn rejects_tampered_signature() {
+    let keys = JwtKeys::new(&config()).unwrap();
+    let token = keys.encode(&user()).unwrap();
+
+    let mut other = config();
+    other.key = JwtKey::Secret(b"fedcba9876543210fedcba9876543210".to_vec());
+    let forged = JwtKeys::new(&other).unwrap().encode(&user()).unwrap();
+    let (payload, _) = token.rsplit_once('.').unwrap();
+    let (_, signature) = forged.rsplit_once('.').unwrap();
+
+    assert!(keys.decode(&format!("{payload}.{signature}")).is_err());
+}
+
+#[test]
+fn rejects_short_secret() {
+    let mut short = config();
+    short.key = JwtKey::Secret(b"too-short".to_vec());
+
+    assert!(matches!(JwtKeys::new(&short), Err(JwtError::Config(_))));
+}
@@ -0,0 +1,62 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::get,
+};
+use middleware::metrics::Metrics;
+use std::time::Duration;
+use tower::ServiceExt;
+
+fn app(metrics: &Metrics) -> Router {
+    let v1 = Router::new()
+        .route("/posts/{id}", get(|| async { "post" }))
+        .route(
+            "/fail",
+            get(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "fail") }),
+        );
+    Router::new().nest("/api/v1", v1).layer(metrics.layer())
+}
+
+#[tokio::test]
+async fn records_requests_by_route_template() {
+    let metrics = Metrics::new().unwrap();
+    for uri in ["/api/v1/posts/1", "/api/v1/posts/2", "/api/v1/fail"] {
+        app(&metrics)
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let output = metrics.render().unwrap();
+    assert!(output.contains(
+        r#"http_requests_total{method="GET",route="/api/v1/posts/{id}",status="200"} 2"#
+    ));
+    assert!(
+        output.contains(r#"http_requests_total{method="GET",route="/api/v1/fail",status="500"} 1"#)
+    );
+    assert!(output.contains(
+        r#"http_request_duration_seconds_count{method="GET",route="/api/v1/posts/{id}",status="200"} 2"#
+    ));
+    assert!(
+        output.contains(r#"http_requests_in_flight{method="GET",route="/api/v1/posts/{id}"} 0"#)
+    );
+}
+
+#[tokio::test]
+async fn records_queries_and_pool() {
+    let metrics = Metrics::new().unwrap();
+    metrics.observe_query("SELECT * FROM post", Duration::from_millis(3), false);
+    metrics.observe_query("insert into post ...", Duration::from_millis(5), true);
+    metrics.set_pool(10, 4);
+
+    let output = metrics.render().unwrap();
+    assert!(
+        output.contains(r#"db_query_duration_seconds_count{operation="SELECT",outcome="ok"} 1"#)
+    );
+    assert!(
+        output.contains(r#"db_query_duration_seconds_count{operation="INSERT",outcome="error"} 1"#)
+    );
+    assert!(output.contains(r#"db_pool_connections{state="active"} 6"#));
+    assert!(output.contains(r#"db_pool_connections{state="idle"} 4"#));
+}
@@ -0,0 +1,80 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+    response::Response,
+    routing::get,
+};
+use middleware::axum::error_response;
+use middleware::context::RequestContext;
+use middleware::tower::{LoggingLayer, X_REQUEST_ID, current_request_id};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/id",
+            get(|ctx: RequestContext| async move {
+                assert_eq!(
+                    current_request_id().as_deref(),
+                    Some(ctx.request_id.as_str())
+                );
+                ctx.request_id
+            }),
+        )
+        .route(
+            "/fail",
+            get(|| async { error_response(StatusCode::NOT_FOUND, "Not found") }),
+        )
+        .layer(LoggingLayer::new())
+}
+
+async fn get_with(uri: &str, request_id: Option<&str>) -> Response {
+    let mut request = Request::get(uri);
+    if let Some(id) = request_id {
+        request = request.header(&X_REQUEST_ID, id);
+    }
+    app()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn body(response: Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn generates_unique_uuid_v7() {
+    let first = get_with("/id", None).await;
+    let id = first.headers()[&X_REQUEST_ID].to_str().unwrap().to_string();
+    assert_eq!(Uuid::parse_str(&id).unwrap().get_version_num(), 7);
+    assert_eq!(body(first).await, id);
+
+    let second = get_with("/id", None).await;
+    assert_ne!(second.headers()[&X_REQUEST_ID], id.as_str());
+}
+
+#[tokio::test]
+async fn honors_valid_incoming_id() {
+    let response = get_with("/id", Some("upstream-42")).await;
+    assert_eq!(response.headers()[&X_REQUEST_ID], "upstream-42");
+    assert_eq!(body(response).await, "upstream-42");
+
+    let response = get_with("/id", Some("bad id\twith spaces")).await;
+    let id = response.headers()[&X_REQUEST_ID].to_str().unwrap();
+    assert!(Uuid::parse_str(id).is_ok());
+}
+
+#[tokio::test]
+async fn error_body_contains_request_id() {
+    let response = get_with("/fail", Some("req-1")).await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    let body: serde_json::Value = serde_json::from_str(&body(response).await).unwrap();
+    assert_eq!(body["request_id"], "req-1");
+    assert_eq!(body["code"], 404);
+}
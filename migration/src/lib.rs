@@ -1,6 +1,9 @@
 pub use sea_orm_migration::prelude::*;
 
 mod post_table;
+mod refresh_token_table;
+mod tag_table;
+mod user_role_column;
 mod user_table;
 
 pub struct Migrator;
@@ -11,6 +14,9 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(user_table::Migration),
             Box::new(post_table::Migration),
+            Box::new(refresh_token_table::Migration),
+            Box::new(user_role_column::Migration),
+            Box::new(tag_table::Migration),
         ]
     }
 }
@@ -14,11 +14,11 @@ impl MigrationTrait for Migration {
         let now = Utc::now();
 
         let seed_data = vec![
-            ("Alice", "alice@example.com", "password123"),
-            ("Bob", "bob@example.com", "password456"),
+            ("Alice", "alice@example.com", "password123", Role::Admin),
+            ("Bob", "bob@example.com", "password456", Role::User),
         ];
 
-        for (name, email, password) in seed_data {
+        for (name, email, password, role) in seed_data {
             // 检查用户是否已存在
             let existing_user = Entity::find()
                 .filter(Column::Email.eq(email))
@@ -31,6 +31,7 @@ impl MigrationTrait for Migration {
                     name: Set(name.to_string()),
                     email: Set(email.to_string()),
                     password: Set(hashed_password),
+                    role: Set(role),
                     created_at: Set(now),
                     updated_at: Set(now),
                     ..Default::default()
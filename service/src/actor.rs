@@ -0,0 +1,33 @@
+use entity::user::{Permission, Role};
+
+/// 发起写操作的已认证用户
+#[derive(Clone, Debug, Default)]
+pub struct Actor {
+    pub user_id: i32,
+    pub role: Role,
+    /// 请求 ID，用于把审计日志与请求日志关联起来
+    pub request_id: Option<String>,
+    /// 客户端地址
+    pub client_ip: Option<String>,
+}
+
+impl Actor {
+    /// 资源所有者，或拥有管理权限的用户可以修改资源
+    pub fn can_modify(&self, owner_id: i32, manage: Permission) -> bool {
+        self.user_id == owner_id || self.role.has(manage)
+    }
+
+    /// 记录一条审计日志，`action` 形如 `post.update`，日志目标为 `audit`
+    pub fn audit(&self, action: &str, resource_id: i32) {
+        tracing::info!(
+            target: "audit",
+            action,
+            resource_id,
+            user_id = self.user_id,
+            role = ?self.role,
+            request_id = self.request_id.as_deref(),
+            client_ip = self.client_ip.as_deref(),
+            "Audit"
+        );
+    }
+}
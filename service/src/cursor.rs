@@ -0,0 +1,88 @@
+//! 游标（keyset）分页
+//!
+//! 按主键排序，以上一页首尾记录的主键为游标继续查询。
+//! 不需要 OFFSET 和 COUNT，翻到很深的页时也只读取一页的数据。
+//!
+//! 只支持主键顺序：主键自增，与创建顺序一致。其他排序（如用户的 `created_at`）
+//! 没有对应的游标，只能按页码分页。
+
+use sea_orm::{ColumnTrait, Order, QueryFilter, QueryOrder, QuerySelect};
+
+/// 游标位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    /// 取排在该主键之后的一页
+    After(i32),
+    /// 取排在该主键之前的一页
+    Before(i32),
+}
+
+/// 一页数据及前后页的游标
+#[derive(Debug, Clone)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    /// 下一页从该主键之后开始，没有更多数据时为空
+    pub next: Option<i32>,
+    /// 上一页从该主键之前开始，已是第一页时为空
+    pub prev: Option<i32>,
+}
+
+/// 为查询加上游标条件、排序和条数限制
+///
+/// `order` 为正常翻页的顺序，向前翻页时反向查询。多取一条记录用于判断是否还有更多数据。
+pub(crate) fn paginate_by_cursor<Q, C>(
+    query: Q,
+    column: C,
+    order: Order,
+    cursor: Option<Cursor>,
+    size: u64,
+) -> Q
+where
+    Q: QueryFilter + QueryOrder + QuerySelect,
+    C: ColumnTrait,
+{
+    let ascending = matches!(order, Order::Asc);
+    let (query, ascending) = match cursor {
+        None => (query, ascending),
+        Some(Cursor::After(id)) if ascending => (query.filter(column.gt(id)), true),
+        Some(Cursor::After(id)) => (query.filter(column.lt(id)), false),
+        Some(Cursor::Before(id)) if ascending => (query.filter(column.lt(id)), false),
+        Some(Cursor::Before(id)) => (query.filter(column.gt(id)), true),
+    };
+    let order = if ascending { Order::Asc } else { Order::Desc };
+    query.order_by(column, order).limit(size + 1)
+}
+
+impl<T> CursorPage<T> {
+    /// 由 `paginate_by_cursor` 查出的记录生成一页，`key` 取记录的主键
+    pub(crate) fn new(
+        mut items: Vec<T>,
+        cursor: Option<Cursor>,
+        size: u64,
+        key: impl Fn(&T) -> i32,
+    ) -> Self {
+        let has_more = items.len() as u64 > size;
+        items.truncate(size as usize);
+        // 向前翻页是反向查询的，恢复正常顺序
+        if let Some(Cursor::Before(_)) = cursor {
+            items.reverse();
+        }
+
+        let first = items.first().map(&key);
+        let last = items.last().map(&key);
+        let (next, prev) = match cursor {
+            None => (last.filter(|_| has_more), None),
+            Some(Cursor::After(_)) => (last.filter(|_| has_more), first),
+            Some(Cursor::Before(_)) => (last, first.filter(|_| has_more)),
+        };
+        CursorPage { items, next, prev }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> CursorPage<U> {
+        CursorPage {
+            items: self.items.into_iter().map(f).collect(),
+            next: self.next,
+            prev: self.prev,
+        }
+    }
+}
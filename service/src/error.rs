@@ -0,0 +1,33 @@
+use sea_orm::DbErr;
+use std::fmt;
+
+/// 业务层错误
+#[derive(Debug)]
+pub enum ServiceError {
+    Db(DbErr),
+    /// 记录不存在
+    NotFound(String),
+    /// 当前用户无权执行该操作
+    Forbidden(String),
+    /// 输入不合法
+    Invalid(String),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::Db(e) => write!(f, "Database error: {e}"),
+            ServiceError::NotFound(msg)
+            | ServiceError::Forbidden(msg)
+            | ServiceError::Invalid(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+impl From<DbErr> for ServiceError {
+    fn from(e: DbErr) -> Self {
+        ServiceError::Db(e)
+    }
+}
@@ -0,0 +1,198 @@
+//! 列表的过滤与排序
+//!
+//! 查询参数语法：
+//!
+//! - `sort=-created_at,name`：逗号分隔的排序字段，`-` 前缀表示降序
+//! - `filter[user_id]=3`：等于
+//! - `filter[title][contains]=rust`：指定运算符，支持 `eq`、`ne`、`gt`、`gte`、
+//!   `lt`、`lte`、`contains`、`in`（逗号分隔）和 `null`（`true` / `false`）
+//!
+//! 字段必须在实体的 `Filterable` 白名单内，`contains` 只能用于其中的文本字段。
+//! 值按列类型解析后作为绑定参数传入，不会拼接进 SQL，`contains` 的值中的
+//! `%` 和 `_` 按字面匹配。
+
+use crate::ServiceError;
+use ::entity::{comment, post, user};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, ColumnType, Condition, EntityTrait, Order, QueryFilter, QueryOrder, Value,
+    sea_query::LikeExpr,
+};
+use std::str::FromStr;
+
+/// 允许过滤和排序的字段，名称与列名一致
+pub trait Filterable: EntityTrait {
+    const FILTERABLE: &'static [&'static str];
+    const SORTABLE: &'static [&'static str];
+    /// 可以使用 `contains` 的文本字段，须同时在 `FILTERABLE` 中
+    const SEARCHABLE: &'static [&'static str];
+}
+
+impl Filterable for post::Entity {
+    const FILTERABLE: &'static [&'static str] = &["id", "user_id", "title"];
+    const SORTABLE: &'static [&'static str] = &["id", "user_id", "title"];
+    const SEARCHABLE: &'static [&'static str] = &["title"];
+}
+
+impl Filterable for user::Entity {
+    const FILTERABLE: &'static [&'static str] = &["id", "name", "email", "role", "created_at"];
+    const SORTABLE: &'static [&'static str] = &["id", "name", "email", "created_at"];
+    const SEARCHABLE: &'static [&'static str] = &["name", "email"];
+}
+
+impl Filterable for comment::Entity {
+    const FILTERABLE: &'static [&'static str] = &["id", "user_id", "deleted_at"];
+    const SORTABLE: &'static [&'static str] = &["id", "user_id"];
+    const SEARCHABLE: &'static [&'static str] = &[];
+}
+
+/// 解析后的过滤与排序条件
+pub struct ListQuery<E: EntityTrait> {
+    condition: Condition,
+    sort: Vec<(E::Column, Order)>,
+}
+
+impl<E: EntityTrait> Default for ListQuery<E> {
+    fn default() -> Self {
+        ListQuery {
+            condition: Condition::all(),
+            sort: Vec::new(),
+        }
+    }
+}
+
+impl<E: Filterable> ListQuery<E> {
+    /// 从查询参数中解析，其他参数忽略
+    pub fn parse<'a>(
+        params: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, ServiceError> {
+        let mut query = ListQuery::default();
+        for (key, value) in params {
+            if key == "sort" {
+                for field in value.split(',').filter(|f| !f.is_empty()) {
+                    let (name, order) = match field.strip_prefix('-') {
+                        Some(name) => (name, Order::Desc),
+                        None => (field.strip_prefix('+').unwrap_or(field), Order::Asc),
+                    };
+                    query
+                        .sort
+                        .push((column::<E>(E::SORTABLE, name, "sort")?, order));
+                }
+            } else if let Some(rest) = key.strip_prefix("filter[") {
+                let (name, op) = match rest.split_once("][") {
+                    Some((name, op)) => (name, op.strip_suffix(']')),
+                    None => (rest.strip_suffix(']').unwrap_or_default(), Some("eq")),
+                };
+                let op = op.ok_or_else(|| invalid(format!("Invalid filter parameter {key}")))?;
+                if op == "contains" {
+                    column::<E>(E::SEARCHABLE, name, "contains")?;
+                }
+                let column = column::<E>(E::FILTERABLE, name, "filter")?;
+                query.condition = query.condition.add(condition(column, name, op, value)?);
+            }
+        }
+        Ok(query)
+    }
+}
+
+impl<E: EntityTrait> ListQuery<E> {
+    /// 是否指定了排序
+    pub fn is_sorted(&self) -> bool {
+        !self.sort.is_empty()
+    }
+
+    /// 加上过滤条件和排序，调用方随后的排序作为次要排序
+    pub(crate) fn apply<Q>(&self, query: Q) -> Q
+    where
+        Q: QueryFilter + QueryOrder,
+    {
+        self.sort
+            .iter()
+            .fold(self.apply_filter(query), |query, (column, order)| {
+                query.order_by(*column, order.clone())
+            })
+    }
+
+    /// 只加上过滤条件，游标分页固定按主键排序
+    pub(crate) fn apply_filter<Q: QueryFilter>(&self, query: Q) -> Q {
+        query.filter(self.condition.clone())
+    }
+}
+
+fn invalid(message: String) -> ServiceError {
+    ServiceError::Invalid(message)
+}
+
+fn column<E: EntityTrait>(
+    allowed: &[&str],
+    name: &str,
+    usage: &str,
+) -> Result<E::Column, ServiceError> {
+    allowed
+        .contains(&name)
+        .then(|| E::Column::from_str(name).ok())
+        .flatten()
+        .ok_or_else(|| invalid(format!("Field {name} cannot be used in {usage}")))
+}
+
+fn condition<C: ColumnTrait>(
+    column: C,
+    name: &str,
+    op: &str,
+    raw: &str,
+) -> Result<Condition, ServiceError> {
+    let value = |raw: &str| {
+        parse_value(column.def().get_column_type(), raw)
+            .ok_or_else(|| invalid(format!("Invalid value for {name}: {raw}")))
+    };
+    let expr = match op {
+        "eq" => column.eq(value(raw)?),
+        "ne" => column.ne(value(raw)?),
+        "gt" => column.gt(value(raw)?),
+        "gte" => column.gte(value(raw)?),
+        "lt" => column.lt(value(raw)?),
+        "lte" => column.lte(value(raw)?),
+        "contains" => {
+            let pattern = format!("%{}%", escape_like(raw));
+            column.like(LikeExpr::new(pattern).escape('\\'))
+        }
+        "in" => column.is_in(raw.split(',').map(value).collect::<Result<Vec<_>, _>>()?),
+        "null" => match raw {
+            "true" => column.is_null(),
+            "false" => column.is_not_null(),
+            _ => return Err(invalid(format!("Invalid value for {name}: {raw}"))),
+        },
+        _ => return Err(invalid(format!("Unknown filter operator {op}"))),
+    };
+    Ok(Condition::all().add(expr))
+}
+
+/// 转义 LIKE 中的通配符，使 `%`、`_` 按字面匹配
+fn escape_like(raw: &str) -> String {
+    let mut escaped = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 按列类型解析查询参数中的值
+fn parse_value(column_type: &ColumnType, raw: &str) -> Option<Value> {
+    Some(match column_type {
+        ColumnType::TinyInteger | ColumnType::SmallInteger | ColumnType::Integer => {
+            raw.parse::<i32>().ok()?.into()
+        }
+        ColumnType::BigInteger => raw.parse::<i64>().ok()?.into(),
+        ColumnType::Boolean => raw.parse::<bool>().ok()?.into(),
+        ColumnType::DateTime | ColumnType::Timestamp | ColumnType::TimestampWithTimeZone => {
+            DateTime::parse_from_rfc3339(raw)
+                .ok()?
+                .with_timezone(&Utc)
+                .into()
+        }
+        _ => raw.to_owned().into(),
+    })
+}
@@ -0,0 +1,286 @@
+//! 按需嵌入的关联数据
+//!
+//! 列表和详情接口通过 `include=author,comments,tags` 指定要嵌入的关联，
+//! 每种关联对整页记录只查询一次，按外键分组后放回各条记录，不会逐条查询。
+
+use crate::{CommentWithAuthor, Query, ServiceError};
+use ::entity::{
+    comment, comment::Entity as Comment, post, post::Entity as Post, post_tag, profile,
+    profile::Entity as Profile, tag, tag::Entity as Tag, user, user::Entity as User,
+};
+use sea_orm::*;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 文章可以嵌入的关联
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PostInclude {
+    /// 作者的公开信息
+    pub author: bool,
+    /// 全部评论及评论作者
+    pub comments: bool,
+    /// 评论数，不需要评论内容时比 `comments` 开销小
+    pub comment_count: bool,
+    pub tags: bool,
+}
+
+impl PostInclude {
+    /// 解析逗号分隔的关联名，未知的关联名返回 `ServiceError::Invalid`
+    pub fn parse(raw: &str) -> Result<Self, ServiceError> {
+        let mut include = PostInclude::default();
+        for name in names(raw) {
+            match name {
+                "author" => include.author = true,
+                "comments" => include.comments = true,
+                "comment_count" => include.comment_count = true,
+                "tags" => include.tags = true,
+                _ => return Err(unknown(name)),
+            }
+        }
+        Ok(include)
+    }
+}
+
+/// 用户可以嵌入的关联
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserInclude {
+    pub profile: bool,
+    /// 用户发表的全部文章
+    pub posts: bool,
+}
+
+impl UserInclude {
+    /// 解析逗号分隔的关联名，未知的关联名返回 `ServiceError::Invalid`
+    pub fn parse(raw: &str) -> Result<Self, ServiceError> {
+        let mut include = UserInclude::default();
+        for name in names(raw) {
+            match name {
+                "profile" => include.profile = true,
+                "posts" => include.posts = true,
+                _ => return Err(unknown(name)),
+            }
+        }
+        Ok(include)
+    }
+}
+
+fn names(raw: &str) -> impl Iterator<Item = &str> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+fn unknown(name: &str) -> ServiceError {
+    ServiceError::Invalid(format!("Unknown include {name}"))
+}
+
+/// 记录及嵌入的关联，序列化时关联字段与记录字段平铺在同一层
+#[derive(Debug, Clone, Serialize)]
+pub struct Embedded<M, R> {
+    #[serde(flatten)]
+    pub model: M,
+    #[serde(flatten)]
+    pub relations: R,
+}
+
+/// 文章作者的公开信息
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct Author {
+    pub id: i32,
+    pub name: String,
+}
+
+/// 文章的关联，未指定嵌入的关联不出现在响应中
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct PostRelations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<Option<Author>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<CommentWithAuthor>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<tag::Model>>,
+}
+
+/// 用户的关联，未指定嵌入的关联不出现在响应中
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct UserRelations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Option<profile::Model>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub posts: Option<Vec<post::Model>>,
+}
+
+impl Query {
+    /// 为文章加载 `include` 指定的关联，每种关联一次查询
+    pub async fn embed_posts(
+        db: &DbConn,
+        posts: Vec<post::Model>,
+        include: PostInclude,
+    ) -> Result<Vec<Embedded<post::Model, PostRelations>>, DbErr> {
+        if posts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<i32> = posts.iter().map(|p| p.id).collect();
+
+        let authors: HashMap<i32, Author> = if include.author {
+            User::find()
+                .filter(user::Column::Id.is_in(posts.iter().map(|p| p.user_id)))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|u| {
+                    (
+                        u.id,
+                        Author {
+                            id: u.id,
+                            name: u.name,
+                        },
+                    )
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut comments: HashMap<i32, Vec<CommentWithAuthor>> = HashMap::new();
+        if include.comments {
+            for comment in Comment::find()
+                .find_also_related(User)
+                .filter(comment::Column::PostId.is_in(ids.clone()))
+                .order_by_asc(comment::Column::Id)
+                .all(db)
+                .await?
+            {
+                let comment = CommentWithAuthor::from(comment);
+                comments
+                    .entry(comment.comment.post_id)
+                    .or_default()
+                    .push(comment);
+            }
+        }
+
+        let counts: HashMap<i32, u64> = if include.comment_count {
+            Comment::find()
+                .select_only()
+                .column(comment::Column::PostId)
+                .column_as(comment::Column::Id.count(), "count")
+                .filter(comment::Column::PostId.is_in(ids.clone()))
+                .group_by(comment::Column::PostId)
+                .into_tuple::<(i32, i64)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|(id, count)| (id, count as u64))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        // 先查关联表，再按标签 id 一次取出全部标签
+        let mut tags: HashMap<i32, Vec<tag::Model>> = HashMap::new();
+        if include.tags {
+            let links = post_tag::Entity::find()
+                .filter(post_tag::Column::PostId.is_in(ids))
+                .all(db)
+                .await?;
+            let by_id: HashMap<i32, tag::Model> = if links.is_empty() {
+                HashMap::new()
+            } else {
+                Tag::find()
+                    .filter(tag::Column::Id.is_in(links.iter().map(|l| l.tag_id)))
+                    .all(db)
+                    .await?
+                    .into_iter()
+                    .map(|t| (t.id, t))
+                    .collect()
+            };
+            for link in links {
+                if let Some(tag) = by_id.get(&link.tag_id) {
+                    tags.entry(link.post_id).or_default().push(tag.clone());
+                }
+            }
+            for post_tags in tags.values_mut() {
+                post_tags.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+        }
+
+        Ok(posts
+            .into_iter()
+            .map(|post| {
+                let relations = PostRelations {
+                    author: include.author.then(|| authors.get(&post.user_id).cloned()),
+                    comments: include
+                        .comments
+                        .then(|| comments.remove(&post.id).unwrap_or_default()),
+                    comment_count: include
+                        .comment_count
+                        .then(|| counts.get(&post.id).copied().unwrap_or_default()),
+                    tags: include
+                        .tags
+                        .then(|| tags.remove(&post.id).unwrap_or_default()),
+                };
+                Embedded {
+                    model: post,
+                    relations,
+                }
+            })
+            .collect())
+    }
+
+    /// 为用户加载 `include` 指定的关联，每种关联一次查询
+    pub async fn embed_users(
+        db: &DbConn,
+        users: Vec<user::Model>,
+        include: UserInclude,
+    ) -> Result<Vec<Embedded<user::Model, UserRelations>>, DbErr> {
+        if users.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<i32> = users.iter().map(|u| u.id).collect();
+
+        let mut profiles: HashMap<i32, profile::Model> = if include.profile {
+            Profile::find()
+                .filter(profile::Column::UserId.is_in(ids.clone()))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|p| (p.user_id, p))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut posts: HashMap<i32, Vec<post::Model>> = HashMap::new();
+        if include.posts {
+            for post in Post::find()
+                .filter(post::Column::UserId.is_in(ids))
+                .order_by_desc(post::Column::Id)
+                .all(db)
+                .await?
+            {
+                posts.entry(post.user_id).or_default().push(post);
+            }
+        }
+
+        Ok(users
+            .into_iter()
+            .map(|user| {
+                let relations = UserRelations {
+                    profile: include.profile.then(|| profiles.remove(&user.id)),
+                    posts: include
+                        .posts
+                        .then(|| posts.remove(&user.id).unwrap_or_default()),
+                };
+                Embedded {
+                    model: user,
+                    relations,
+                }
+            })
+            .collect())
+    }
+}
@@ -1,9 +1,19 @@
+mod actor;
+mod cursor;
 mod delete;
+mod error;
+mod filter;
+mod include;
 mod insert;
 mod mutation;
 mod query;
 mod save;
+pub use actor::*;
+pub use cursor::*;
 pub use delete::*;
+pub use error::*;
+pub use filter::*;
+pub use include::*;
 pub use insert::*;
 pub use mutation::*;
 pub use query::*;
@@ -1,6 +1,11 @@
+use crate::{Actor, CommentWithAuthor, ServiceError};
+use ::entity::user::Permission;
 use ::entity::{
-    comment, comment::Entity as Comment, post, post::Entity as Post, user, user::Entity as User,
+    comment, comment::Entity as Comment, post, post::Entity as Post, post_tag,
+    post_tag::Entity as PostTag, refresh_token, refresh_token::Entity as RefreshToken, tag,
+    tag::Entity as Tag, user, user::Entity as User,
 };
+use chrono::{DateTime, Utc};
 use sea_orm::*;
 
 pub struct Mutation;
@@ -20,35 +25,159 @@ impl Mutation {
         .await
     }
 
-    pub async fn update_post_by_id(
+    /// 查找文章并检查当前用户是否可以修改它
+    async fn find_post_for_update(
         db: &DbConn,
+        actor: &Actor,
         id: i32,
-        form_data: post::Model,
-    ) -> Result<post::Model, DbErr> {
-        let post: post::ActiveModel = Post::find_by_id(id)
+    ) -> Result<post::Model, ServiceError> {
+        let post = Post::find_by_id(id)
             .one(db)
             .await?
-            .ok_or(DbErr::Custom("Cannot find post.".to_owned()))
-            .map(Into::into)?;
+            .ok_or(ServiceError::NotFound("Cannot find post.".to_owned()))?;
 
-        post::ActiveModel {
+        if !actor.can_modify(post.user_id, Permission::ManagePosts) {
+            return Err(ServiceError::Forbidden(
+                "You can only modify your own posts.".to_owned(),
+            ));
+        }
+
+        Ok(post)
+    }
+
+    /// 修改文章标题和正文，作者保持不变
+    pub async fn update_post_by_id(
+        db: &DbConn,
+        actor: &Actor,
+        id: i32,
+        form_data: post::Model,
+    ) -> Result<post::Model, ServiceError> {
+        let post: post::ActiveModel = Self::find_post_for_update(db, actor, id).await?.into();
+
+        let post = post::ActiveModel {
             id: post.id,
             title: Set(form_data.title.to_owned()),
             body: Set(form_data.body.to_owned()),
-            user_id: Set(form_data.user_id),
+            user_id: post.user_id,
         }
         .update(db)
-        .await
+        .await?;
+        actor.audit("post.update", id);
+
+        Ok(post)
     }
 
-    pub async fn delete_post(db: &DbConn, id: i32) -> Result<DeleteResult, DbErr> {
-        let post: post::ActiveModel = Post::find_by_id(id)
+    pub async fn delete_post(
+        db: &DbConn,
+        actor: &Actor,
+        id: i32,
+    ) -> Result<DeleteResult, ServiceError> {
+        let post: post::ActiveModel = Self::find_post_for_update(db, actor, id).await?.into();
+
+        let result = post.delete(db).await?;
+        actor.audit("post.delete", id);
+
+        Ok(result)
+    }
+
+    /// 按名称查找标签，不存在时创建
+    ///
+    /// 并发创建同名标签时，后插入的一方会违反 slug 的唯一索引，这时改为读取已创建的标签
+    async fn find_or_create_tag<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        name: &str,
+    ) -> Result<tag::Model, ServiceError> {
+        let slug = slugify(name);
+        if slug.is_empty() {
+            return Err(ServiceError::Invalid(format!("Invalid tag name: {name:?}")));
+        }
+
+        if let Some(tag) = Tag::find()
+            .filter(tag::Column::Slug.eq(&slug))
             .one(db)
             .await?
-            .ok_or(DbErr::Custom("Cannot find post.".to_owned()))
-            .map(Into::into)?;
+        {
+            return Ok(tag);
+        }
 
-        post.delete(db).await
+        // 插入放在保存点中，唯一索引冲突时只回滚这一步，外层事务仍可继续使用
+        let savepoint = db.begin().await?;
+        let inserted = tag::ActiveModel {
+            name: Set(name.trim().to_owned()),
+            slug: Set(slug.clone()),
+            ..Default::default()
+        }
+        .insert(&savepoint)
+        .await;
+        match inserted {
+            Ok(tag) => {
+                savepoint.commit().await?;
+                Ok(tag)
+            }
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                savepoint.rollback().await?;
+                // 加锁读取最新提交的数据，MySQL 的一致性读看不到事务开始后其他事务插入的行
+                Tag::find()
+                    .filter(tag::Column::Slug.eq(&slug))
+                    .lock_shared()
+                    .one(db)
+                    .await?
+                    .ok_or(ServiceError::Db(e))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 为文章添加标签，返回文章当前的全部标签
+    pub async fn attach_tags(
+        db: &DbConn,
+        actor: &Actor,
+        post_id: i32,
+        names: &[String],
+    ) -> Result<Vec<tag::Model>, ServiceError> {
+        Self::find_post_for_update(db, actor, post_id).await?;
+
+        let txn = db.begin().await?;
+        for name in names {
+            let tag = Self::find_or_create_tag(&txn, name).await?;
+            if PostTag::find_by_id((post_id, tag.id))
+                .one(&txn)
+                .await?
+                .is_none()
+            {
+                PostTag::insert(post_tag::ActiveModel {
+                    post_id: Set(post_id),
+                    tag_id: Set(tag.id),
+                })
+                .exec_without_returning(&txn)
+                .await?;
+            }
+        }
+        txn.commit().await?;
+        actor.audit("post.tags.attach", post_id);
+
+        Ok(crate::Query::find_tags_by_post_id(db, post_id).await?)
+    }
+
+    /// 移除文章上的标签
+    pub async fn detach_tag(
+        db: &DbConn,
+        actor: &Actor,
+        post_id: i32,
+        slug: &str,
+    ) -> Result<DeleteResult, ServiceError> {
+        Self::find_post_for_update(db, actor, post_id).await?;
+
+        let tag = Tag::find()
+            .filter(tag::Column::Slug.eq(slug))
+            .one(db)
+            .await?
+            .ok_or(ServiceError::NotFound("Cannot find tag.".to_owned()))?;
+
+        let result = PostTag::delete_by_id((post_id, tag.id)).exec(db).await?;
+        actor.audit("post.tags.detach", post_id);
+
+        Ok(result)
     }
 
     pub async fn delete_all_posts(db: &DbConn) -> Result<DeleteResult, DbErr> {
@@ -63,6 +192,7 @@ impl Mutation {
             name: Set(form_data.name.to_owned()),
             email: Set(form_data.email.to_owned()),
             password: Set(form_data.password.to_owned()),
+            role: Set(form_data.role),
             created_at: Set(form_data.created_at),
             updated_at: Set(form_data.updated_at),
             ..Default::default()
@@ -79,7 +209,7 @@ impl Mutation {
         let user: user::ActiveModel = User::find_by_id(id)
             .one(db)
             .await?
-            .ok_or(DbErr::Custom("Cannot find user.".to_owned()))
+            .ok_or(DbErr::RecordNotFound("Cannot find user.".to_owned()))
             .map(Into::into)?;
 
         user::ActiveModel {
@@ -87,6 +217,7 @@ impl Mutation {
             name: Set(form_data.name.to_owned()),
             email: Set(form_data.email.to_owned()),
             password: Set(form_data.password.to_owned()),
+            role: user.role,
             created_at: Set(form_data.created_at),
             updated_at: Set(form_data.updated_at),
         }
@@ -94,52 +225,249 @@ impl Mutation {
         .await
     }
 
+    pub async fn update_user_role(
+        db: &DbConn,
+        id: i32,
+        role: user::Role,
+    ) -> Result<user::Model, DbErr> {
+        let mut user: user::ActiveModel = User::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Cannot find user.".to_owned()))
+            .map(Into::into)?;
+
+        user.role = Set(role);
+        user.updated_at = Set(Utc::now());
+        user.update(db).await
+    }
+
     pub async fn delete_all_users(db: &DbConn) -> Result<DeleteResult, DbErr> {
         User::delete_many().exec(db).await
     }
 
+    /// 发表评论，指定 `parent_id` 时作为对同一文章下另一条评论的回复
     pub async fn create_comment(
         db: &DbConn,
         form_data: comment::Model,
-    ) -> Result<comment::ActiveModel, DbErr> {
-        comment::ActiveModel {
+    ) -> Result<comment::ActiveModel, ServiceError> {
+        if let Some(parent_id) = form_data.parent_id {
+            let parent = Comment::find_by_id(parent_id)
+                .filter(comment::Column::PostId.eq(form_data.post_id))
+                .one(db)
+                .await?
+                .ok_or(ServiceError::NotFound(
+                    "Cannot find parent comment.".to_owned(),
+                ))?;
+            if parent.deleted_at.is_some() {
+                return Err(ServiceError::Invalid(
+                    "Cannot reply to a deleted comment.".to_owned(),
+                ));
+            }
+        }
+
+        Ok(comment::ActiveModel {
             content: Set(form_data.content.to_owned()),
             user_id: Set(form_data.user_id),
             post_id: Set(form_data.post_id),
+            parent_id: Set(form_data.parent_id),
+            deleted_at: Set(None),
             ..Default::default()
         }
         .save(db)
-        .await
+        .await?)
     }
 
-    pub async fn update_comment_by_id(
+    /// 查找属于指定文章的评论及其作者，并检查当前用户是否可以修改它
+    async fn find_comment_for_update(
         db: &DbConn,
+        actor: &Actor,
+        post_id: i32,
         id: i32,
-        form_data: comment::Model,
-    ) -> Result<comment::Model, DbErr> {
-        let comment: comment::ActiveModel = Comment::find_by_id(id)
+    ) -> Result<(comment::Model, Option<user::Model>), ServiceError> {
+        let (comment, author) = Comment::find_by_id(id)
+            .find_also_related(User)
+            .filter(comment::Column::PostId.eq(post_id))
+            .filter(comment::Column::DeletedAt.is_null())
             .one(db)
             .await?
-            .ok_or(DbErr::Custom("Cannot find comment.".to_owned()))
-            .map(Into::into)?;
+            .ok_or(ServiceError::NotFound("Cannot find comment.".to_owned()))?;
 
-        comment::ActiveModel {
+        if !actor.can_modify(comment.user_id, Permission::ManageComments) {
+            return Err(ServiceError::Forbidden(
+                "You can only modify your own comments.".to_owned(),
+            ));
+        }
+
+        Ok((comment, author))
+    }
+
+    /// 修改评论内容，作者和所属文章保持不变
+    pub async fn update_comment_by_id(
+        db: &DbConn,
+        actor: &Actor,
+        post_id: i32,
+        id: i32,
+        form_data: comment::Model,
+    ) -> Result<CommentWithAuthor, ServiceError> {
+        let (comment, author) = Self::find_comment_for_update(db, actor, post_id, id).await?;
+        let comment: comment::ActiveModel = comment.into();
+
+        let comment = comment::ActiveModel {
             id: comment.id,
             content: Set(form_data.content.to_owned()),
-            user_id: Set(form_data.user_id),
-            post_id: Set(form_data.post_id),
+            user_id: comment.user_id,
+            post_id: comment.post_id,
+            parent_id: comment.parent_id,
+            deleted_at: comment.deleted_at,
         }
         .update(db)
-        .await
+        .await?;
+        actor.audit("comment.update", id);
+
+        Ok((comment, author).into())
     }
 
-    pub async fn delete_comment(db: &DbConn, id: i32) -> Result<DeleteResult, DbErr> {
-        let comment: comment::ActiveModel = Comment::find_by_id(id)
-            .one(db)
+    /// 删除评论
+    ///
+    /// 仍有回复的评论只清空内容并标记删除时间，保留在树中作为占位；
+    /// 没有回复的评论直接删除，随后依次清理因此不再有回复的已删除祖先。
+    pub async fn delete_comment(
+        db: &DbConn,
+        actor: &Actor,
+        post_id: i32,
+        id: i32,
+    ) -> Result<(), ServiceError> {
+        let (comment, _) = Self::find_comment_for_update(db, actor, post_id, id).await?;
+
+        let txn = db.begin().await?;
+        if Self::has_replies(&txn, comment.id).await? {
+            comment::ActiveModel {
+                id: Set(comment.id),
+                content: Set(String::new()),
+                deleted_at: Set(Some(Utc::now())),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await?;
+        } else {
+            let mut parent_id = comment.parent_id;
+            Comment::delete_by_id(comment.id).exec(&txn).await?;
+
+            while let Some(id) = parent_id {
+                let Some(parent) = Comment::find_by_id(id).one(&txn).await? else {
+                    break;
+                };
+                if parent.deleted_at.is_none() || Self::has_replies(&txn, parent.id).await? {
+                    break;
+                }
+                parent_id = parent.parent_id;
+                Comment::delete_by_id(parent.id).exec(&txn).await?;
+            }
+        }
+        txn.commit().await?;
+        actor.audit("comment.delete", id);
+
+        Ok(())
+    }
+
+    async fn has_replies<C: ConnectionTrait>(db: &C, id: i32) -> Result<bool, DbErr> {
+        Ok(Comment::find()
+            .filter(comment::Column::ParentId.eq(id))
+            .count(db)
             .await?
-            .ok_or(DbErr::Custom("Cannot find comment.".to_owned()))
-            .map(Into::into)?;
+            > 0)
+    }
+
+    pub async fn create_refresh_token(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        token_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<refresh_token::Model, DbErr> {
+        refresh_token::ActiveModel {
+            user_id: Set(user_id),
+            token_hash: Set(token_hash),
+            expires_at: Set(expires_at),
+            revoked_at: Set(None),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// 吊销旧的刷新令牌并签发新令牌
+    ///
+    /// 只有未被吊销的令牌才能被轮换，并发使用同一令牌时只有一个请求会成功，
+    /// 其余请求返回 `DbErr::RecordNotUpdated`。
+    pub async fn rotate_refresh_token(
+        db: &DbConn,
+        old_id: i32,
+        user_id: i32,
+        token_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<refresh_token::Model, DbErr> {
+        let txn = db.begin().await?;
 
-        comment.delete(db).await
+        let revoked = RefreshToken::update_many()
+            .col_expr(
+                refresh_token::Column::RevokedAt,
+                sea_query::Expr::value(Utc::now()),
+            )
+            .filter(refresh_token::Column::Id.eq(old_id))
+            .filter(refresh_token::Column::RevokedAt.is_null())
+            .exec(&txn)
+            .await?;
+        if revoked.rows_affected == 0 {
+            return Err(DbErr::RecordNotUpdated);
+        }
+
+        let token = Self::create_refresh_token(&txn, user_id, token_hash, expires_at).await?;
+        txn.commit().await?;
+
+        Ok(token)
+    }
+
+    pub async fn revoke_refresh_token(db: &DbConn, id: i32) -> Result<UpdateResult, DbErr> {
+        RefreshToken::update_many()
+            .col_expr(
+                refresh_token::Column::RevokedAt,
+                sea_query::Expr::value(Utc::now()),
+            )
+            .filter(refresh_token::Column::Id.eq(id))
+            .filter(refresh_token::Column::RevokedAt.is_null())
+            .exec(db)
+            .await
+    }
+
+    /// 吊销用户的全部刷新令牌，用于检测到令牌被重放时
+    pub async fn revoke_user_refresh_tokens(
+        db: &DbConn,
+        user_id: i32,
+    ) -> Result<UpdateResult, DbErr> {
+        RefreshToken::update_many()
+            .col_expr(
+                refresh_token::Column::RevokedAt,
+                sea_query::Expr::value(Utc::now()),
+            )
+            .filter(refresh_token::Column::UserId.eq(user_id))
+            .filter(refresh_token::Column::RevokedAt.is_null())
+            .exec(db)
+            .await
+    }
+}
+
+/// 由标签名生成 URL 中使用的 slug，例如 `"Rust Web"` -> `"rust-web"`
+///
+/// 保留各种文字的字母和数字，其余字符合并为一个 `-`，结果为空时说明名称不可用
+pub(crate) fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
     }
+    slug.trim_end_matches('-').to_owned()
 }
@@ -1,30 +1,122 @@
+use crate::cursor::{Cursor, CursorPage, paginate_by_cursor};
+use crate::filter::ListQuery;
 use ::entity::{
-    comment, comment::Entity as Comment, post, post::Entity as Post, user, user::Entity as User,
+    comment, comment::Entity as Comment, post, post::Entity as Post, post_tag, refresh_token,
+    refresh_token::Entity as RefreshToken, tag, tag::Entity as Tag, user, user::Entity as User,
 };
+use chrono::{DateTime, Utc};
 use sea_orm::*;
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
 
 pub struct Query;
 
+/// 带作者名的评论，已删除的评论不输出 `user_id` 和 `content`
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct CommentWithAuthor {
+    #[serde(flatten, serialize_with = "serialize_comment")]
+    pub comment: comment::Model,
+    /// 已删除的评论为 `[deleted]`
+    pub author_name: String,
+}
+
+impl From<(comment::Model, Option<user::Model>)> for CommentWithAuthor {
+    fn from((comment, author): (comment::Model, Option<user::Model>)) -> Self {
+        // 已删除的评论不再展示作者
+        let author_name = match author {
+            _ if comment.deleted_at.is_some() => "[deleted]".to_owned(),
+            Some(author) => author.name,
+            None => "Unknown".to_owned(),
+        };
+        CommentWithAuthor {
+            comment,
+            author_name,
+        }
+    }
+}
+
+/// 已删除的评论只保留在评论树中的位置
+fn serialize_comment<S: Serializer>(
+    comment: &comment::Model,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Tombstone<'a> {
+        id: i32,
+        post_id: i32,
+        parent_id: Option<i32>,
+        deleted_at: &'a Option<DateTime<Utc>>,
+    }
+
+    match comment.deleted_at {
+        None => comment.serialize(serializer),
+        Some(_) => Tombstone {
+            id: comment.id,
+            post_id: comment.post_id,
+            parent_id: comment.parent_id,
+            deleted_at: &comment.deleted_at,
+        }
+        .serialize(serializer),
+    }
+}
+
+/// 评论树中的一个节点
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct CommentNode {
+    #[serde(flatten)]
+    pub comment: CommentWithAuthor,
+    /// 直接回复的总数，可能多于 `replies` 中返回的数量
+    pub reply_count: u64,
+    #[cfg_attr(feature = "utoipa", schema(no_recursion))]
+    pub replies: Vec<CommentNode>,
+}
+
 impl Query {
     /// 通过id查找posts 包括所有评论
     pub async fn find_post_by_id(db: &DbConn, id: i32) -> Result<Option<post::ModelEx>, DbErr> {
         Post::load().filter_by_id(id).with(Comment).one(db).await
     }
 
-    /// If ok, returns (post models, num pages).
+    /// 通过id查找posts 不加载评论
+    pub async fn find_post_model_by_id(db: &DbConn, id: i32) -> Result<Option<post::Model>, DbErr> {
+        Post::find_by_id(id).one(db).await
+    }
+
+    /// If ok, returns (post models, num items and pages).
     pub async fn find_posts_in_page(
         db: &DbConn,
+        list: &ListQuery<Post>,
         page: u64,
         size: u64,
-    ) -> Result<(Vec<post::Model>, u64), DbErr> {
+    ) -> Result<(Vec<post::Model>, ItemsAndPagesNumber), DbErr> {
         // Setup paginator
-        let paginator = Post::find()
+        let paginator = list
+            .apply(Post::find())
             .order_by_asc(post::Column::Id)
             .paginate(db, size);
-        let num_pages = paginator.num_pages().await?;
+        let totals = paginator.num_items_and_pages().await?;
 
         // Fetch paginated posts
-        paginator.fetch_page(page - 1).await.map(|p| (p, num_pages))
+        paginator
+            .fetch_page(page.saturating_sub(1))
+            .await
+            .map(|p| (p, totals))
+    }
+
+    /// 按游标查询一页文章，按 id 升序
+    pub async fn find_posts_with_cursor(
+        db: &DbConn,
+        list: &ListQuery<Post>,
+        cursor: Option<Cursor>,
+        size: u64,
+    ) -> Result<CursorPage<post::Model>, DbErr> {
+        let query = list.apply_filter(Post::find());
+        let posts = paginate_by_cursor(query, post::Column::Id, Order::Asc, cursor, size)
+            .all(db)
+            .await?;
+        Ok(CursorPage::new(posts, cursor, size, |p| p.id))
     }
 
     pub async fn find_user_by_id(db: &DbConn, id: i32) -> Result<Option<user::Model>, DbErr> {
@@ -41,19 +133,48 @@ impl Query {
             .await
     }
 
+    pub async fn find_refresh_token_by_hash(
+        db: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<refresh_token::Model>, DbErr> {
+        RefreshToken::find()
+            .filter(refresh_token::Column::TokenHash.eq(token_hash))
+            .one(db)
+            .await
+    }
+
     pub async fn find_users_in_page(
         db: &DbConn,
+        list: &ListQuery<User>,
         page: u64,
         users_per_page: u64,
-    ) -> Result<(Vec<user::Model>, u64), DbErr> {
+    ) -> Result<(Vec<user::Model>, ItemsAndPagesNumber), DbErr> {
         // Setup paginator
-        let paginator = User::find()
+        let paginator = list
+            .apply(User::find())
             .order_by_asc(user::Column::Id)
             .paginate(db, users_per_page);
-        let num_pages = paginator.num_pages().await?;
+        let totals = paginator.num_items_and_pages().await?;
 
         // Fetch paginated users
-        paginator.fetch_page(page - 1).await.map(|p| (p, num_pages))
+        paginator
+            .fetch_page(page.saturating_sub(1))
+            .await
+            .map(|p| (p, totals))
+    }
+
+    /// 按游标查询一页用户，按 id 升序
+    pub async fn find_users_with_cursor(
+        db: &DbConn,
+        list: &ListQuery<User>,
+        cursor: Option<Cursor>,
+        size: u64,
+    ) -> Result<CursorPage<user::Model>, DbErr> {
+        let query = list.apply_filter(User::find());
+        let users = paginate_by_cursor(query, user::Column::Id, Order::Asc, cursor, size)
+            .all(db)
+            .await?;
+        Ok(CursorPage::new(users, cursor, size, |u| u.id))
     }
 
     pub async fn find_posts_by_user_id(
@@ -66,6 +187,39 @@ impl Query {
             .await
     }
 
+    /// 分页查询用户的文章，按 id 降序
+    pub async fn find_posts_by_user_id_in_page(
+        db: &DbConn,
+        user_id: i32,
+        page: u64,
+        size: u64,
+    ) -> Result<(Vec<post::Model>, ItemsAndPagesNumber), DbErr> {
+        let paginator = Post::find()
+            .filter(post::Column::UserId.eq(user_id))
+            .order_by_desc(post::Column::Id)
+            .paginate(db, size);
+        let totals = paginator.num_items_and_pages().await?;
+
+        paginator
+            .fetch_page(page.saturating_sub(1))
+            .await
+            .map(|p| (p, totals))
+    }
+
+    /// 按游标查询用户的文章，按 id 降序
+    pub async fn find_posts_by_user_id_with_cursor(
+        db: &DbConn,
+        user_id: i32,
+        cursor: Option<Cursor>,
+        size: u64,
+    ) -> Result<CursorPage<post::Model>, DbErr> {
+        let query = Post::find().filter(post::Column::UserId.eq(user_id));
+        let posts = paginate_by_cursor(query, post::Column::Id, Order::Desc, cursor, size)
+            .all(db)
+            .await?;
+        Ok(CursorPage::new(posts, cursor, size, |p| p.id))
+    }
+
     pub async fn find_comment_by_id(db: &DbConn, id: i32) -> Result<Option<comment::Model>, DbErr> {
         Comment::find_by_id(id).one(db).await
     }
@@ -83,31 +237,315 @@ impl Query {
         keyword: &str,
         page: u64,
         posts_per_page: u64,
-    ) -> Result<(Vec<post::Model>, u64), DbErr> {
+    ) -> Result<(Vec<post::Model>, ItemsAndPagesNumber), DbErr> {
         let paginator = Post::find()
-            .filter(
-                Condition::any()
-                    .add(post::Column::Title.contains(keyword))
-                    .add(post::Column::Body.contains(keyword)),
-            )
+            .filter(keyword_condition(keyword))
             .order_by_desc(post::Column::Id)
             .paginate(db, posts_per_page);
 
-        let num_pages = paginator.num_pages().await?;
-        paginator.fetch_page(page - 1).await.map(|p| (p, num_pages))
+        let totals = paginator.num_items_and_pages().await?;
+        paginator
+            .fetch_page(page.saturating_sub(1))
+            .await
+            .map(|p| (p, totals))
+    }
+
+    /// 按游标搜索文章，按 id 降序
+    pub async fn search_posts_with_cursor(
+        db: &DbConn,
+        keyword: &str,
+        cursor: Option<Cursor>,
+        size: u64,
+    ) -> Result<CursorPage<post::Model>, DbErr> {
+        let query = Post::find().filter(keyword_condition(keyword));
+        let posts = paginate_by_cursor(query, post::Column::Id, Order::Desc, cursor, size)
+            .all(db)
+            .await?;
+        Ok(CursorPage::new(posts, cursor, size, |p| p.id))
+    }
+
+    /// 通过id查找评论及其作者
+    pub async fn find_comment_with_author_by_id(
+        db: &DbConn,
+        id: i32,
+    ) -> Result<Option<CommentWithAuthor>, DbErr> {
+        Comment::find_by_id(id)
+            .find_also_related(User)
+            .one(db)
+            .await
+            .map(|c| c.map(Into::into))
     }
+
+    /// 分页查询文章的全部评论，作者通过 JOIN 一并取出
     pub async fn find_comments_by_post_id_in_page(
         db: &DbConn,
         post_id: i32,
         page: u64,
         comments_per_page: u64,
-    ) -> Result<(Vec<comment::Model>, u64), DbErr> {
+    ) -> Result<(Vec<CommentWithAuthor>, ItemsAndPagesNumber), DbErr> {
         let paginator = Comment::find()
+            .find_also_related(User)
             .filter(comment::Column::PostId.eq(post_id))
             .order_by_asc(comment::Column::Id)
             .paginate(db, comments_per_page);
-        let num_pages = paginator.num_pages().await?;
+        let totals = paginator.num_items_and_pages().await?;
+
+        paginator
+            .fetch_page(page.saturating_sub(1))
+            .await
+            .map(|p| (p.into_iter().map(Into::into).collect(), totals))
+    }
+
+    /// 按游标查询文章的全部评论，按 id 升序
+    pub async fn find_comments_by_post_id_with_cursor(
+        db: &DbConn,
+        post_id: i32,
+        cursor: Option<Cursor>,
+        size: u64,
+    ) -> Result<CursorPage<CommentWithAuthor>, DbErr> {
+        let query = Comment::find()
+            .find_also_related(User)
+            .filter(comment::Column::PostId.eq(post_id));
+        let comments = paginate_by_cursor(query, comment::Column::Id, Order::Asc, cursor, size)
+            .all(db)
+            .await?;
+        Ok(CursorPage::new(comments, cursor, size, |(c, _)| c.id).map(Into::into))
+    }
+
+    /// 分页查询评论树
+    ///
+    /// `parent_id` 为空时从文章的顶层评论开始，否则从该评论的回复开始。
+    /// 起始层按 `list` 过滤排序后按 `page`/`size` 分页，其下最多展开 `depth` 层回复，
+    /// 每条评论只带前 `replies_per_level` 条回复，其余回复通过 `reply_count`
+    /// 提示客户端以该评论为 `parent_id` 继续分页获取。
+    /// 每层先统计回复数量，再用窗口函数取出每条评论的前几条回复，
+    /// 评论作者通过 JOIN 一并取出。
+    #[allow(clippy::too_many_arguments)]
+    pub async fn find_comment_tree_in_page(
+        db: &DbConn,
+        post_id: i32,
+        parent_id: Option<i32>,
+        list: &ListQuery<Comment>,
+        page: u64,
+        size: u64,
+        depth: u32,
+        replies_per_level: u64,
+    ) -> Result<(Vec<CommentNode>, ItemsAndPagesNumber), DbErr> {
+        let paginator = list
+            .apply(comment_level(post_id, parent_id))
+            .order_by_asc(comment::Column::Id)
+            .paginate(db, size);
+        let totals = paginator.num_items_and_pages().await?;
+        let roots: Vec<CommentWithAuthor> = paginator
+            .fetch_page(page.saturating_sub(1))
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
 
-        paginator.fetch_page(page - 1).await.map(|p| (p, num_pages))
+        let tree = expand_comment_tree(db, roots, depth, replies_per_level).await?;
+        Ok((tree, totals))
     }
+
+    /// 按游标查询评论树，起始层按 id 升序，其余同 `find_comment_tree_in_page`
+    #[allow(clippy::too_many_arguments)]
+    pub async fn find_comment_tree_with_cursor(
+        db: &DbConn,
+        post_id: i32,
+        parent_id: Option<i32>,
+        list: &ListQuery<Comment>,
+        cursor: Option<Cursor>,
+        size: u64,
+        depth: u32,
+        replies_per_level: u64,
+    ) -> Result<CursorPage<CommentNode>, DbErr> {
+        let query = list.apply_filter(comment_level(post_id, parent_id));
+        let roots = paginate_by_cursor(query, comment::Column::Id, Order::Asc, cursor, size)
+            .all(db)
+            .await?;
+        let CursorPage { items, next, prev } =
+            CursorPage::new(roots, cursor, size, |(c, _)| c.id).map(CommentWithAuthor::from);
+
+        let items = expand_comment_tree(db, items, depth, replies_per_level).await?;
+        Ok(CursorPage { items, next, prev })
+    }
+
+    pub async fn find_all_tags(db: &DbConn) -> Result<Vec<tag::Model>, DbErr> {
+        Tag::find().order_by_asc(tag::Column::Name).all(db).await
+    }
+
+    pub async fn find_tag_by_slug(db: &DbConn, slug: &str) -> Result<Option<tag::Model>, DbErr> {
+        Tag::find().filter(tag::Column::Slug.eq(slug)).one(db).await
+    }
+
+    /// 分页查询带有指定标签的文章，返回 (文章, 总数与总页数)
+    pub async fn find_posts_by_tag_in_page(
+        db: &DbConn,
+        tag: &tag::Model,
+        list: &ListQuery<Post>,
+        page: u64,
+        size: u64,
+    ) -> Result<(Vec<post::Model>, ItemsAndPagesNumber), DbErr> {
+        let paginator = list
+            .apply(tag.find_related(Post))
+            .order_by_desc(post::Column::Id)
+            .paginate(db, size);
+        let totals = paginator.num_items_and_pages().await?;
+
+        paginator
+            .fetch_page(page.saturating_sub(1))
+            .await
+            .map(|p| (p, totals))
+    }
+
+    /// 按游标查询带有指定标签的文章，按 id 降序
+    pub async fn find_posts_by_tag_with_cursor(
+        db: &DbConn,
+        tag: &tag::Model,
+        list: &ListQuery<Post>,
+        cursor: Option<Cursor>,
+        size: u64,
+    ) -> Result<CursorPage<post::Model>, DbErr> {
+        let query = list.apply_filter(tag.find_related(Post));
+        let posts = paginate_by_cursor(query, post::Column::Id, Order::Desc, cursor, size)
+            .all(db)
+            .await?;
+        Ok(CursorPage::new(posts, cursor, size, |p| p.id))
+    }
+
+    pub async fn find_tags_by_post_id(db: &DbConn, post_id: i32) -> Result<Vec<tag::Model>, DbErr> {
+        Tag::find()
+            .filter(
+                tag::Column::Id.in_subquery(
+                    sea_query::Query::select()
+                        .column(post_tag::Column::TagId)
+                        .from(post_tag::Entity)
+                        .and_where(post_tag::Column::PostId.eq(post_id))
+                        .to_owned(),
+                ),
+            )
+            .order_by_asc(tag::Column::Name)
+            .all(db)
+            .await
+    }
+}
+
+/// 文章下某一层的评论及其作者，`parent_id` 为空时为顶层评论
+fn comment_level(post_id: i32, parent_id: Option<i32>) -> SelectTwo<comment::Entity, user::Entity> {
+    let parent_filter = match parent_id {
+        Some(id) => comment::Column::ParentId.eq(id),
+        None => comment::Column::ParentId.is_null(),
+    };
+    Comment::find()
+        .find_also_related(User)
+        .filter(comment::Column::PostId.eq(post_id))
+        .filter(parent_filter)
+}
+
+/// 标题或正文包含关键字
+fn keyword_condition(keyword: &str) -> Condition {
+    Condition::any()
+        .add(post::Column::Title.contains(keyword))
+        .add(post::Column::Body.contains(keyword))
+}
+
+/// 在起始层评论下展开至多 `depth` 层回复
+async fn expand_comment_tree(
+    db: &DbConn,
+    roots: Vec<CommentWithAuthor>,
+    depth: u32,
+    replies_per_level: u64,
+) -> Result<Vec<CommentNode>, DbErr> {
+    // 逐层取出回复，levels[n] 保存第 n 层评论按父评论分组后的回复
+    let mut levels: Vec<HashMap<i32, Vec<CommentWithAuthor>>> = Vec::new();
+    let mut counts: HashMap<i32, u64> = HashMap::new();
+    let mut ids: Vec<i32> = roots.iter().map(|c| c.comment.id).collect();
+    while !ids.is_empty() {
+        let level_counts = count_replies(db, ids).await?;
+        // 只有带回复的评论需要继续展开
+        ids = level_counts.keys().copied().collect();
+        counts.extend(level_counts);
+        if ids.is_empty() || levels.len() == depth as usize || replies_per_level == 0 {
+            break;
+        }
+
+        let mut children: HashMap<i32, Vec<CommentWithAuthor>> = HashMap::new();
+        for reply in Comment::find()
+            .find_also_related(User)
+            .filter(comment::Column::Id.in_subquery(first_replies(ids, replies_per_level)))
+            .order_by_asc(comment::Column::Id)
+            .all(db)
+            .await?
+        {
+            let reply = CommentWithAuthor::from(reply);
+            if let Some(parent_id) = reply.comment.parent_id {
+                children.entry(parent_id).or_default().push(reply);
+            }
+        }
+        ids = children.values().flatten().map(|c| c.comment.id).collect();
+        levels.push(children);
+    }
+
+    Ok(build_comment_tree(roots, &mut levels, &counts))
+}
+
+/// 按父评论统计直接回复的数量，没有回复的评论不在结果中
+async fn count_replies(db: &DbConn, parent_ids: Vec<i32>) -> Result<HashMap<i32, u64>, DbErr> {
+    Ok(Comment::find()
+        .select_only()
+        .column(comment::Column::ParentId)
+        .column_as(comment::Column::Id.count(), "reply_count")
+        .filter(comment::Column::ParentId.is_in(parent_ids))
+        .group_by(comment::Column::ParentId)
+        .into_tuple::<(Option<i32>, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|(id, count)| Some((id?, count as u64)))
+        .collect())
+}
+
+/// 每条父评论下按 id 升序的前 `limit` 条回复的 id，用窗口函数在数据库中截取
+fn first_replies(parent_ids: Vec<i32>, limit: u64) -> sea_query::SelectStatement {
+    let mut ranked = Comment::find()
+        .select_only()
+        .column(comment::Column::Id)
+        .filter(comment::Column::ParentId.is_in(parent_ids))
+        .into_query();
+    ranked.expr_window_as(
+        sea_query::Expr::cust("ROW_NUMBER()"),
+        sea_query::WindowStatement::partition_by(comment::Column::ParentId)
+            .order_by(comment::Column::Id, Order::Asc)
+            .to_owned(),
+        sea_query::Alias::new("rn"),
+    );
+    sea_query::Query::select()
+        .column(comment::Column::Id)
+        .from_subquery(ranked, sea_query::Alias::new("ranked"))
+        .and_where(sea_query::Expr::col(sea_query::Alias::new("rn")).lte(limit as i64))
+        .to_owned()
+}
+
+fn build_comment_tree(
+    comments: Vec<CommentWithAuthor>,
+    levels: &mut [HashMap<i32, Vec<CommentWithAuthor>>],
+    counts: &HashMap<i32, u64>,
+) -> Vec<CommentNode> {
+    comments
+        .into_iter()
+        .map(|comment| {
+            let id = comment.comment.id;
+            let replies = match levels.split_first_mut() {
+                Some((children, rest)) => {
+                    build_comment_tree(children.remove(&id).unwrap_or_default(), rest, counts)
+                }
+                None => Vec::new(),
+            };
+            CommentNode {
+                comment,
+                reply_count: counts.get(&id).copied().unwrap_or_default(),
+                replies,
+            }
+        })
+        .collect()
 }
@@ -0,0 +1,144 @@
+#![cfg(feature = "mock")]
+use chrono::Utc;
+use entity::{comment, user, user::Role};
+use sea_orm::*;
+use service::{Actor, CommentWithAuthor, ListQuery, Mutation, Query, ServiceError};
+use std::collections::BTreeMap;
+
+fn comment(id: i32, parent_id: Option<i32>) -> comment::Model {
+    comment::Model {
+        id,
+        user_id: 1,
+        post_id: 1,
+        parent_id,
+        content: format!("Comment {id}"),
+        deleted_at: None,
+    }
+}
+
+fn author() -> user::Model {
+    user::Model {
+        id: 1,
+        name: "Alice".to_owned(),
+        email: "alice@example.com".to_owned(),
+        password: String::new(),
+        role: Role::User,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn count(n: i64) -> BTreeMap<String, Value> {
+    BTreeMap::from([("num_items".to_owned(), Value::BigInt(Some(n)))])
+}
+
+/// 按父评论分组统计的回复数量
+fn reply_count(parent_id: i32, n: i64) -> BTreeMap<String, Value> {
+    BTreeMap::from([
+        ("parent_id".to_owned(), Value::Int(Some(parent_id))),
+        ("reply_count".to_owned(), Value::BigInt(Some(n))),
+    ])
+}
+
+#[tokio::test]
+async fn reply_to_deleted_comment_is_rejected() {
+    let deleted = comment::Model {
+        content: String::new(),
+        deleted_at: Some(Utc::now()),
+        ..comment(1, None)
+    };
+    let db = &MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[deleted]])
+        .into_connection();
+
+    let result = Mutation::create_comment(db, comment(0, Some(1))).await;
+    assert!(matches!(result, Err(ServiceError::Invalid(_))));
+}
+
+#[tokio::test]
+async fn deleting_comment_with_replies_leaves_tombstone() {
+    let owner = Actor {
+        user_id: 1,
+        role: Role::User,
+        ..Default::default()
+    };
+    let tombstone = comment::Model {
+        content: String::new(),
+        deleted_at: Some(Utc::now()),
+        ..comment(1, None)
+    };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[(comment(1, None), Some(author()))]])
+        .append_query_results([[count(1)]])
+        .append_query_results([[tombstone]])
+        .into_connection();
+
+    Mutation::delete_comment(&db, &owner, 1, 1).await.unwrap();
+
+    let log = db.into_transaction_log();
+    let executed = |sql: &str| {
+        log.iter()
+            .flat_map(|t| t.statements())
+            .any(|s| s.sql.contains(sql))
+    };
+    assert!(executed(r#"UPDATE "comment""#));
+    assert!(!executed(r#"DELETE FROM "comment""#));
+}
+
+#[tokio::test]
+async fn comment_tree_limits_replies_in_sql() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[count(1)]])
+        .append_query_results([[(comment(1, None), Some(author()))]])
+        .append_query_results([[reply_count(1, 3)]])
+        // 三条回复中数据库只返回前两条
+        .append_query_results([[
+            (comment(2, Some(1)), Some(author())),
+            (comment(3, Some(1)), Some(author())),
+        ]])
+        .append_query_results([Vec::<BTreeMap<String, Value>>::new()])
+        .into_connection();
+
+    let (tree, _) =
+        Query::find_comment_tree_in_page(&db, 1, None, &ListQuery::default(), 1, 10, 1, 2)
+            .await
+            .unwrap();
+
+    assert_eq!(tree.len(), 1);
+    assert_eq!(tree[0].reply_count, 3);
+    let replies: Vec<_> = tree[0]
+        .replies
+        .iter()
+        .map(|r| r.comment.comment.id)
+        .collect();
+    assert_eq!(replies, [2, 3]);
+    assert_eq!(tree[0].replies[0].reply_count, 0);
+
+    let log = db.into_transaction_log();
+    let statement = log.iter().flat_map(|t| t.statements()).nth(3).unwrap();
+    // 每条评论的回复数在数据库中按窗口函数截取
+    assert!(statement.sql.contains("ROW_NUMBER() OVER"));
+    assert!(statement.sql.contains(r#"WHERE "rn" <= $2"#));
+}
+
+#[test]
+fn tombstone_hides_author_and_content() {
+    let serialize = |comment: comment::Model| {
+        serde_json::to_value(CommentWithAuthor::from((comment, Some(author())))).unwrap()
+    };
+
+    let live = serialize(comment(1, None));
+    assert_eq!(live["user_id"], 1);
+    assert_eq!(live["content"], "Comment 1");
+    assert_eq!(live["author_name"], "Alice");
+
+    let tombstone = serialize(comment::Model {
+        deleted_at: Some(Utc::now()),
+        ..comment(1, None)
+    });
+    assert!(tombstone.get("user_id").is_none());
+    assert!(tombstone.get("content").is_none());
+    assert_eq!(tombstone["author_name"], "[deleted]");
+    assert_eq!(tombstone["id"], 1);
+    assert!(tombstone["deleted_at"].is_string());
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "mock")]
+use entity::post;
+use sea_orm::*;
+use service::{Cursor, ListQuery, Query};
+
+fn post(id: i32) -> post::Model {
+    post::Model {
+        id,
+        title: format!("Title {id}"),
+        body: format!("Text {id}"),
+        user_id: 1,
+    }
+}
+
+fn executed_sql(db: DatabaseConnection) -> String {
+    db.into_transaction_log()
+        .iter()
+        .flat_map(|t| t.statements())
+        .map(|s| s.sql.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn next_page_after_cursor() {
+    // 多取的一条表示还有下一页
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[post(4), post(5), post(6)]])
+        .into_connection();
+
+    let page = Query::find_posts_with_cursor(&db, &ListQuery::default(), Some(Cursor::After(3)), 2)
+        .await
+        .unwrap();
+
+    assert_eq!(page.items.iter().map(|p| p.id).collect::<Vec<_>>(), [4, 5]);
+    assert_eq!(page.next, Some(5));
+    assert_eq!(page.prev, Some(4));
+
+    let sql = executed_sql(db);
+    assert!(sql.contains(r#"WHERE "post"."id" > $1 ORDER BY "post"."id" ASC LIMIT $2"#));
+}
+
+#[tokio::test]
+async fn previous_page_before_cursor() {
+    // 向前翻页按 id 降序查询，只查到两条说明已是第一页
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[post(2), post(1)]])
+        .into_connection();
+
+    let page =
+        Query::find_posts_with_cursor(&db, &ListQuery::default(), Some(Cursor::Before(3)), 2)
+            .await
+            .unwrap();
+
+    assert_eq!(page.items.iter().map(|p| p.id).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(page.next, Some(2));
+    assert_eq!(page.prev, None);
+
+    let sql = executed_sql(db);
+    assert!(sql.contains(r#"WHERE "post"."id" < $1 ORDER BY "post"."id" DESC LIMIT $2"#));
+}
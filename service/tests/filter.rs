@@ -0,0 +1,86 @@
+#![cfg(feature = "mock")]
+use entity::post;
+use sea_orm::*;
+use service::{ListQuery, Query, ServiceError};
+use std::collections::BTreeMap;
+
+fn parse(query: &[(&str, &str)]) -> Result<ListQuery<post::Entity>, ServiceError> {
+    ListQuery::parse(query.iter().copied())
+}
+
+#[test]
+fn rejects_fields_outside_whitelist() {
+    assert!(matches!(
+        parse(&[("filter[body]", "x")]),
+        Err(ServiceError::Invalid(_))
+    ));
+    assert!(matches!(
+        parse(&[("sort", "-body")]),
+        Err(ServiceError::Invalid(_))
+    ));
+    assert!(matches!(
+        parse(&[("filter[title][like]", "x")]),
+        Err(ServiceError::Invalid(_))
+    ));
+    assert!(matches!(
+        parse(&[("filter[user_id]", "abc")]),
+        Err(ServiceError::Invalid(_))
+    ));
+    // contains 只能用于文本字段
+    assert!(matches!(
+        parse(&[("filter[user_id][contains]", "1")]),
+        Err(ServiceError::Invalid(_))
+    ));
+}
+
+#[tokio::test]
+async fn filters_and_sorts_posts() {
+    let list = parse(&[
+        ("sort", "-title"),
+        ("filter[title][contains]", "rust"),
+        ("filter[user_id]", "3"),
+        ("page", "1"),
+    ])
+    .unwrap();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[BTreeMap::from([(
+            "num_items".to_owned(),
+            Value::BigInt(Some(0)),
+        )])]])
+        .append_query_results([Vec::<post::Model>::new()])
+        .into_connection();
+
+    Query::find_posts_in_page(&db, &list, 1, 10).await.unwrap();
+
+    let sql: String = db
+        .into_transaction_log()
+        .iter()
+        .flat_map(|t| t.statements())
+        .map(|s| s.sql.clone())
+        .collect();
+    assert!(sql.contains(r#""post"."title" LIKE $1"#));
+    assert!(sql.contains(r#""post"."user_id" = $2"#));
+    assert!(sql.contains(r#"ORDER BY "post"."title" DESC, "post"."id" ASC"#));
+}
+
+#[tokio::test]
+async fn contains_matches_wildcards_literally() {
+    let list = parse(&[("filter[title][contains]", r"50%_off\")]).unwrap();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[BTreeMap::from([(
+            "num_items".to_owned(),
+            Value::BigInt(Some(0)),
+        )])]])
+        .append_query_results([Vec::<post::Model>::new()])
+        .into_connection();
+
+    Query::find_posts_in_page(&db, &list, 1, 10).await.unwrap();
+
+    let log = db.into_transaction_log();
+    let count = log.iter().flat_map(|t| t.statements()).next().unwrap();
+    assert!(count.sql.contains(r#""post"."title" LIKE $1 ESCAPE"#));
+    assert_eq!(
+        count.values.as_ref().unwrap().0[0],
+        Value::from(r"%50\%\_off\\%")
+    );
+}
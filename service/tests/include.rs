@@ -0,0 +1,73 @@
+#![cfg(feature = "mock")]
+use chrono::Utc;
+use entity::{post, post_tag, tag, user, user::Role};
+use sea_orm::*;
+use service::{PostInclude, Query, ServiceError};
+
+fn post(id: i32) -> post::Model {
+    post::Model {
+        id,
+        title: format!("Title {id}"),
+        body: format!("Text {id}"),
+        user_id: 1,
+    }
+}
+
+fn tag(id: i32, name: &str) -> tag::Model {
+    tag::Model {
+        id,
+        name: name.to_owned(),
+        slug: name.to_owned(),
+    }
+}
+
+#[test]
+fn unknown_include_is_rejected() {
+    assert!(matches!(
+        PostInclude::parse("author,likes"),
+        Err(ServiceError::Invalid(_))
+    ));
+}
+
+#[tokio::test]
+async fn includes_are_loaded_once_per_relation() {
+    let author = user::Model {
+        id: 1,
+        name: "Alice".to_owned(),
+        email: "alice@example.com".to_owned(),
+        password: String::new(),
+        role: Role::User,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    let links = [(1, 2), (1, 1)].map(|(post_id, tag_id)| post_tag::Model { post_id, tag_id });
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[author]])
+        .append_query_results([links])
+        .append_query_results([[tag(1, "rust"), tag(2, "axum")]])
+        .into_connection();
+
+    let include = PostInclude::parse("author,tags").unwrap();
+    let posts = Query::embed_posts(&db, vec![post(1), post(2), post(3)], include)
+        .await
+        .unwrap();
+
+    assert!(posts.iter().all(|p| p.relations.comments.is_none()));
+    let authors: Vec<_> = posts
+        .iter()
+        .map(|p| p.relations.author.clone().flatten().map(|a| a.name))
+        .collect();
+    assert_eq!(authors, vec![Some("Alice".to_owned()); 3]);
+    let tags: Vec<_> = posts[0]
+        .relations
+        .tags
+        .iter()
+        .flatten()
+        .map(|t| t.name.as_str())
+        .collect();
+    assert_eq!(tags, ["axum", "rust"]);
+    assert_eq!(posts[1].relations.tags.as_deref(), Some(&[][..]));
+
+    // 三篇文章只需要作者、关联表和标签各一次查询
+    assert_eq!(db.into_transaction_log().len(), 3);
+}
@@ -1,12 +1,22 @@
 mod prepare;
 
-use entity::post;
+use entity::{post, user::Role};
 use prepare::prepare_mock_db;
-use service::{Mutation, Query};
+use service::{Actor, Mutation, Query, ServiceError};
 
 #[tokio::test]
 async fn main() {
     let db = &prepare_mock_db();
+    let owner = Actor {
+        user_id: 1,
+        role: Role::User,
+        ..Default::default()
+    };
+    let other = Actor {
+        user_id: 2,
+        role: Role::User,
+        ..Default::default()
+    };
 
     {
         let post = Query::find_post_by_id(db, 1).await.unwrap().unwrap();
@@ -26,7 +36,7 @@ async fn main() {
             post::Model {
                 id: 0,
                 title: "Title D".to_owned(),
-                text: "Text D".to_owned(),
+                body: "Text D".to_owned(),
                 user_id: 1,
             },
         )
@@ -38,20 +48,38 @@ async fn main() {
             post::ActiveModel {
                 id: sea_orm::ActiveValue::Unchanged(6),
                 title: sea_orm::ActiveValue::Unchanged("Title D".to_owned()),
-                text: sea_orm::ActiveValue::Unchanged("Text D".to_owned()),
+                body: sea_orm::ActiveValue::Unchanged("Text D".to_owned()),
                 user_id: sea_orm::ActiveValue::Unchanged(3),
             }
         );
     }
 
+    {
+        let result = Mutation::update_post_by_id(
+            db,
+            &other,
+            1,
+            post::Model {
+                id: 1,
+                title: "Stolen Title A".to_owned(),
+                body: "Stolen Text A".to_owned(),
+                user_id: 2,
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+    }
+
     {
         let post = Mutation::update_post_by_id(
             db,
+            &owner,
             1,
             post::Model {
                 id: 1,
                 title: "New Title A".to_owned(),
-                text: "New Text A".to_owned(),
+                body: "New Text A".to_owned(),
                 user_id: 1,
             },
         )
@@ -63,14 +91,14 @@ async fn main() {
             post::Model {
                 id: 1,
                 title: "New Title A".to_owned(),
-                text: "New Text A".to_owned(),
+                body: "New Text A".to_owned(),
                 user_id: 1,
             }
         );
     }
 
     {
-        let result = Mutation::delete_post(db, 5).await.unwrap();
+        let result = Mutation::delete_post(db, &other, 5).await.unwrap();
 
         assert_eq!(result.rows_affected, 1);
     }
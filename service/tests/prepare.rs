@@ -8,37 +8,43 @@ pub fn prepare_mock_db() -> DatabaseConnection {
             [post::Model {
                 id: 1,
                 title: "Title A".to_owned(),
-                text: "Text A".to_owned(),
+                body: "Text A".to_owned(),
                 user_id: 1,
             }],
             [post::Model {
                 id: 5,
                 title: "Title C".to_owned(),
-                text: "Text C".to_owned(),
+                body: "Text C".to_owned(),
                 user_id: 2,
             }],
             [post::Model {
                 id: 6,
                 title: "Title D".to_owned(),
-                text: "Text D".to_owned(),
+                body: "Text D".to_owned(),
                 user_id: 3,
             }],
             [post::Model {
                 id: 1,
                 title: "Title A".to_owned(),
-                text: "Text A".to_owned(),
+                body: "Text A".to_owned(),
+                user_id: 1,
+            }],
+            [post::Model {
+                id: 1,
+                title: "Title A".to_owned(),
+                body: "Text A".to_owned(),
                 user_id: 1,
             }],
             [post::Model {
                 id: 1,
                 title: "New Title A".to_owned(),
-                text: "New Text A".to_owned(),
+                body: "New Text A".to_owned(),
                 user_id: 1,
             }],
             [post::Model {
                 id: 5,
                 title: "Title C".to_owned(),
-                text: "Text C".to_owned(),
+                body: "Text C".to_owned(),
                 user_id: 2,
             }],
         ])
@@ -0,0 +1,145 @@
+#![cfg(feature = "mock")]
+use ::entity::{post, post_tag, tag, user::Role};
+use sea_orm::*;
+use service::{Actor, Mutation, ServiceError};
+
+fn owner() -> Actor {
+    Actor {
+        user_id: 1,
+        role: Role::User,
+        ..Default::default()
+    }
+}
+
+fn post() -> post::Model {
+    post::Model {
+        id: 1,
+        title: "Title A".to_owned(),
+        body: "Text A".to_owned(),
+        user_id: 1,
+    }
+}
+
+fn rust() -> tag::Model {
+    tag::Model {
+        id: 1,
+        name: "Rust".to_owned(),
+        slug: "rust".to_owned(),
+    }
+}
+
+/// 事务日志中插入 `table` 的语句数
+fn inserts(db: DatabaseConnection, table: &str) -> usize {
+    let insert = format!(r#"INSERT INTO "{table}""#);
+    db.into_transaction_log()
+        .iter()
+        .flat_map(|t| t.statements())
+        .filter(|s| s.sql.contains(&insert))
+        .count()
+}
+
+/// `attach_tags` 按 `name` 查找标签时使用的 slug
+async fn slug_of(name: &str) -> Value {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[post()]])
+        .append_query_results([[rust()]])
+        .append_query_results([[post_tag::Model {
+            post_id: 1,
+            tag_id: 1,
+        }]])
+        .append_query_results([[rust()]])
+        .into_connection();
+
+    Mutation::attach_tags(&db, &owner(), 1, &[name.to_owned()])
+        .await
+        .unwrap();
+
+    let log = db.into_transaction_log();
+    let lookup = log
+        .iter()
+        .flat_map(|t| t.statements())
+        .find(|s| s.sql.contains(r#"WHERE "tag"."slug" = $1"#))
+        .unwrap();
+    lookup.values.as_ref().unwrap().0[0].clone()
+}
+
+#[tokio::test]
+async fn tag_names_are_slugified() {
+    let cases = [
+        ("Rust Web", "rust-web"),
+        ("  SeaORM  ", "seaorm"),
+        // 连续的标点和空白合并为一个 `-`，首尾的去掉
+        ("C++ & Rust!!", "c-rust"),
+        ("--async/await--", "async-await"),
+        ("Ünïcode Straße", "ünïcode-straße"),
+        ("数据库 设计", "数据库-设计"),
+    ];
+    for (name, slug) in cases {
+        assert_eq!(slug_of(name).await, Value::from(slug), "{name:?}");
+    }
+}
+
+#[tokio::test]
+async fn reattaching_existing_tag_inserts_nothing() {
+    let attached = post_tag::Model {
+        post_id: 1,
+        tag_id: 1,
+    };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[post()]])
+        // "Rust" 和 " rust " 的 slug 相同，都找到已有的标签和关联
+        .append_query_results([[rust()]])
+        .append_query_results([[attached.clone()]])
+        .append_query_results([[rust()]])
+        .append_query_results([[attached]])
+        .append_query_results([[rust()]])
+        .into_connection();
+
+    let names = ["Rust".to_owned(), " rust ".to_owned()];
+    let tags = Mutation::attach_tags(&db, &owner(), 1, &names)
+        .await
+        .unwrap();
+
+    assert_eq!(tags, vec![rust()]);
+    assert_eq!(inserts(db, "post_tag"), 0);
+}
+
+#[tokio::test]
+async fn attaching_new_tag_creates_it_once() {
+    let web = tag::Model {
+        id: 2,
+        name: "Rust Web".to_owned(),
+        slug: "rust-web".to_owned(),
+    };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[post()]])
+        .append_query_results([Vec::<tag::Model>::new()])
+        .append_query_results([[web.clone()]])
+        .append_query_results([Vec::<post_tag::Model>::new()])
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }])
+        .append_query_results([[rust(), web.clone()]])
+        .into_connection();
+
+    let tags = Mutation::attach_tags(&db, &owner(), 1, &["Rust Web".to_owned()])
+        .await
+        .unwrap();
+
+    assert_eq!(tags, vec![rust(), web]);
+    assert_eq!(inserts(db, "post_tag"), 1);
+}
+
+#[tokio::test]
+async fn invalid_tag_name_is_rejected() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[post()], [post()]])
+        .into_connection();
+
+    for name in ["!!!", "   "] {
+        let result = Mutation::attach_tags(&db, &owner(), 1, &[name.to_owned()]).await;
+        assert!(matches!(result, Err(ServiceError::Invalid(_))), "{name:?}");
+    }
+    assert_eq!(inserts(db, "tag"), 0);
+}
@@ -0,0 +1,385 @@
+//! A .env file parser
+//!
+//! Supported syntax:
+//!
+//! - `KEY=VALUE` lines, optionally prefixed with `export`
+//! - `#` comments, both on their own line and after a value (an unquoted value
+//!   needs whitespace before the `#`)
+//! - single-quoted values, taken literally and possibly spanning several lines
+//! - double-quoted values, possibly spanning several lines, with the escapes
+//!   `\n`, `\r`, `\t`, `\"`, `\\` and `\$`
+//! - `${VAR}` interpolation in unquoted and double-quoted values, resolved
+//!   against the keys defined earlier and then the process environment;
+//!   undefined variables expand to an empty string
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::io;
+use std::iter::Peekable;
+use std::path::PathBuf;
+use std::str::Chars;
+
+/// A syntax error in a .env file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number where the offending entry starts
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An error loading a .env file
+#[derive(Debug)]
+pub enum Error {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, source: ParseError },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "Failed to read {}: {source}", path.display()),
+            Error::Parse { path, source } => write!(f, "{}: {source}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Parses the content of a .env file, interpolating from the process environment
+pub fn parse(content: &str) -> Result<HashMap<String, String>, ParseError> {
+    parse_with(content, |name| env::var(name).ok())
+}
+
+/// Parses the content of a .env file
+///
+/// `${VAR}` is resolved against the keys defined earlier in `content` first,
+/// then against `lookup`.
+pub fn parse_with(
+    content: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<HashMap<String, String>, ParseError> {
+    let mut parser = Parser {
+        chars: content.chars().peekable(),
+        line: 1,
+        vars: HashMap::new(),
+        lookup,
+    };
+    parser.parse()?;
+    Ok(parser.vars)
+}
+
+/// Loads `.env` and the profile-specific `.env.{profile}` from a directory
+///
+/// ```ignore
+/// let vars = Dotenv::new().profile("test").read()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Dotenv {
+    dir: PathBuf,
+    profile: Option<String>,
+    override_existing: bool,
+}
+
+impl Default for Dotenv {
+    fn default() -> Self {
+        Dotenv {
+            dir: PathBuf::from("."),
+            profile: None,
+            override_existing: false,
+        }
+    }
+}
+
+impl Dotenv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory containing the files, the current directory by default
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Also loads `.env.{profile}`, e.g. `.env.development` or `.env.test`,
+    /// whose values take precedence over `.env`
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into()).filter(|p| !p.is_empty());
+        self
+    }
+
+    /// Whether `load` replaces variables already set in the process environment,
+    /// `false` by default
+    pub fn override_existing(mut self, override_existing: bool) -> Self {
+        self.override_existing = override_existing;
+        self
+    }
+
+    /// Paths of the files to load, in load order
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.dir.join(".env")];
+        if let Some(profile) = &self.profile {
+            paths.push(self.dir.join(format!(".env.{profile}")));
+        }
+        paths
+    }
+
+    /// Reads the files without touching the process environment
+    ///
+    /// Missing files are skipped.
+    pub fn read(&self) -> Result<HashMap<String, String>, Error> {
+        let mut vars = HashMap::new();
+        for path in self.paths() {
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(Error::Io { path, source }),
+            };
+            let file_vars = parse_with(&content, |name| {
+                vars.get(name).cloned().or_else(|| env::var(name).ok())
+            })
+            .map_err(|source| Error::Parse {
+                path: path.clone(),
+                source,
+            })?;
+            vars.extend(file_vars);
+        }
+        Ok(vars)
+    }
+
+    /// Reads the files and sets the variables in the process environment
+    ///
+    /// Variables that are already set are kept unless `override_existing` is enabled.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`std::env::set_var`]: no other thread may read or write the
+    /// environment concurrently, so call this at the start of `main` before
+    /// spawning threads or starting an async runtime.
+    pub unsafe fn load(&self) -> Result<(), Error> {
+        for (key, value) in self.read()? {
+            if self.override_existing || env::var_os(&key).is_none() {
+                unsafe { env::set_var(key, value) };
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads the .env file in the current directory into the process environment
+///
+/// Variables that are already set are kept. A missing file is not an error.
+///
+/// # Safety
+///
+/// See [`Dotenv::load`].
+pub unsafe fn dotenv() -> Result<(), Error> {
+    unsafe { Dotenv::new().load() }
+}
+
+struct Parser<'a, F> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    vars: HashMap<String, String>,
+    lookup: F,
+}
+
+impl<F: Fn(&str) -> Option<String>> Parser<'_, F> {
+    fn parse(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_while(char::is_whitespace);
+            match self.chars.peek() {
+                None => return Ok(()),
+                Some('#') => self.skip_line(),
+                Some(_) => self.entry()?,
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_while(&mut self, f: impl Fn(char) -> bool) {
+        while self.chars.peek().is_some_and(|&c| f(c)) {
+            self.next();
+        }
+    }
+
+    fn skip_line(&mut self) {
+        self.skip_while(|c| c != '\n');
+    }
+
+    fn error(line: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line,
+            message: message.into(),
+        }
+    }
+
+    fn entry(&mut self) -> Result<(), ParseError> {
+        let line = self.line;
+        let mut key = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '=' || c == '\n' {
+                break;
+            }
+            key.push(c);
+            self.next();
+        }
+        if self.chars.peek() != Some(&'=') {
+            return Err(Self::error(line, "expected KEY=VALUE"));
+        }
+        self.next();
+
+        let key = key.trim();
+        let key = key
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .map_or(key, str::trim_start);
+        let valid = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if !valid {
+            return Err(Self::error(line, format!("invalid key `{key}`")));
+        }
+
+        self.skip_while(|c| c == ' ' || c == '\t');
+        let value = match self.chars.peek() {
+            Some('"') => self.double_quoted(line)?,
+            Some('\'') => self.single_quoted(line)?,
+            _ => self.unquoted(line)?,
+        };
+        self.vars.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    fn unquoted(&mut self, line: usize) -> Result<String, ParseError> {
+        let mut value = String::new();
+        let mut after_space = true;
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '\n' => break,
+                '#' if after_space => {
+                    self.skip_line();
+                    break;
+                }
+                '$' => {
+                    self.next();
+                    value.push_str(&self.interpolate(line)?);
+                }
+                _ => {
+                    value.push(c);
+                    self.next();
+                }
+            }
+            after_space = c == ' ' || c == '\t';
+        }
+        Ok(value.trim_end().to_string())
+    }
+
+    fn single_quoted(&mut self, line: usize) -> Result<String, ParseError> {
+        self.next();
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => break,
+                Some(c) => value.push(c),
+                None => return Err(Self::error(line, "unterminated single quote")),
+            }
+        }
+        self.end_of_quoted(line)?;
+        Ok(value)
+    }
+
+    fn double_quoted(&mut self, line: usize) -> Result<String, ParseError> {
+        self.next();
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => match self.next() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => return Err(Self::error(line, "unterminated double quote")),
+                },
+                Some('$') => value.push_str(&self.interpolate(line)?),
+                Some(c) => value.push(c),
+                None => return Err(Self::error(line, "unterminated double quote")),
+            }
+        }
+        self.end_of_quoted(line)?;
+        Ok(value)
+    }
+
+    /// Only whitespace and a comment may follow the closing quote
+    fn end_of_quoted(&mut self, line: usize) -> Result<(), ParseError> {
+        self.skip_while(|c| c == ' ' || c == '\t');
+        match self.chars.peek() {
+            None | Some('\n') => Ok(()),
+            Some('#') => {
+                self.skip_line();
+                Ok(())
+            }
+            Some(_) => Err(Self::error(
+                line,
+                "unexpected characters after quoted value",
+            )),
+        }
+    }
+
+    /// Expands `${VAR}` after the `$` has been consumed, a lone `$` is kept as is
+    fn interpolate(&mut self, line: usize) -> Result<String, ParseError> {
+        if self.chars.peek() != Some(&'{') {
+            return Ok("$".to_string());
+        }
+        self.next();
+        let mut name = String::new();
+        loop {
+            match self.chars.peek() {
+                Some('}') => {
+                    self.next();
+                    break;
+                }
+                Some(&c) if c.is_ascii_alphanumeric() || c == '_' || c == '.' => {
+                    name.push(c);
+                    self.next();
+                }
+                _ => return Err(Self::error(line, "unterminated ${...}")),
+            }
+        }
+        Ok(self
+            .vars
+            .get(&name)
+            .cloned()
+            .or_else(|| (self.lookup)(&name))
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use uitls::{Dotenv, parse_with};
+
+fn parse(content: &str) -> HashMap<String, String> {
+    parse_with(content, |_| None).unwrap()
+}
+
+#[test]
+fn plain_export_and_comments() {
+    let vars = parse(
+        "# comment\n\
+         export HOST=127.0.0.1\n\
+         PORT = 3000 # inline\n\
+         URL=http://x/#anchor\n\
+         EMPTY=\n",
+    );
+
+    assert_eq!(vars["HOST"], "127.0.0.1");
+    assert_eq!(vars["PORT"], "3000");
+    assert_eq!(vars["URL"], "http://x/#anchor");
+    assert_eq!(vars["EMPTY"], "");
+}
+
+#[test]
+fn quoted_values() {
+    let vars = parse(
+        "SINGLE='a ${X} \\n' # comment\n\
+         DOUBLE=\"tab\\tquote\\\" \\${X}\"\n\
+         MULTI=\"line 1\nline 2\"\n",
+    );
+
+    assert_eq!(vars["SINGLE"], "a ${X} \\n");
+    assert_eq!(vars["DOUBLE"], "tab\tquote\" ${X}");
+    assert_eq!(vars["MULTI"], "line 1\nline 2");
+}
+
+#[test]
+fn interpolation() {
+    let vars = parse_with(
+        "USER=app\nURL=mysql://${USER}@${DB_HOST}/db\nGREETING=\"hi ${USER}\"\nMISSING=${NOPE}\n",
+        |name| (name == "DB_HOST").then(|| "localhost".to_string()),
+    )
+    .unwrap();
+
+    assert_eq!(vars["URL"], "mysql://app@localhost/db");
+    assert_eq!(vars["GREETING"], "hi app");
+    assert_eq!(vars["MISSING"], "");
+}
+
+#[test]
+fn errors_have_line_numbers() {
+    let err = |content| parse_with(content, |_| None).unwrap_err();
+
+    assert_eq!(err("A=1\nB=\"foo'\n").line, 2);
+    assert_eq!(err("A=1\n\nNOT A PAIR\n").line, 3);
+    assert_eq!(err("A='x' y\n").line, 1);
+    assert_eq!(err("1A=x\n").message, "invalid key `1A`");
+}
+
+#[test]
+fn profile_file_overrides_base() {
+    let dir = std::env::temp_dir().join(format!("uitls-dotenv-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(".env"), "NAME=base\nLEVEL=info\n").unwrap();
+    std::fs::write(dir.join(".env.test"), "LEVEL=debug\nLABEL=${NAME}-test\n").unwrap();
+
+    let vars = Dotenv::new().dir(&dir).profile("test").read().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(vars["NAME"], "base");
+    assert_eq!(vars["LEVEL"], "debug");
+    assert_eq!(vars["LABEL"], "base-test");
+}